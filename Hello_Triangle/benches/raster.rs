@@ -0,0 +1,101 @@
+//frame time benchmark, run with `cargo bench --bench raster`.
+//renders the helmet the viewer shows when the asset is there, and a dense sphere that always works.
+//the helmet isn't in the repository, get it from the Khronos sample models with
+//  mkdir -p Assets/Helmet && cd Assets/Helmet && for f in DamagedHelmet.gltf DamagedHelmet.bin Default_albedo.jpg \
+//      Default_AO.jpg Default_emissive.jpg Default_metalRoughness.jpg Default_normal.jpg; do
+//    curl -LO https://raw.githubusercontent.com/KhronosGroup/glTF-Sample-Assets/main/Models/DamagedHelmet/glTF/$f; done
+//without it a stand-in of the same size gets drawn instead: as many triangles, a 2048x2048 trilinear texture
+//and about the same area on screen
+use glam::Mat4;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use Hello_Triangle::*;
+
+const WIDTH: usize = 480;
+const HEIGHT: usize = 480;
+const WARMUP_FRAMES: usize = 3;
+const FRAMES: usize = 30;
+//DamagedHelmet has 46356 triangles, 114 * 204 * 2 is close enough
+const STAND_IN_RINGS: u32 = 114;
+const STAND_IN_SEGMENTS: u32 = 204;
+const STAND_IN_TEXTURE_SIZE: usize = 2048;
+
+//median frame time, less noisy than the mean when the machine is busy
+fn measure(mut frame: impl FnMut()) -> Duration {
+    for _ in 0..WARMUP_FRAMES {
+        frame();
+    }
+    let mut times: Vec<Duration> = (0..FRAMES)
+        .map(|_| {
+            let start = Instant::now();
+            frame();
+            start.elapsed()
+        })
+        .collect();
+    times.sort();
+    times[FRAMES / 2]
+}
+
+//smooth color gradients with a fine checker on top, so every mip level holds something different
+fn stand_in_texture(size: usize) -> Texture {
+    let data = (0..size * size)
+        .map(|id| {
+            let (x, y) = index_to_coords(id, size);
+            let checker = if (x / 8 + y / 8) % 2 == 0 { 255 } else { 160 };
+            to_argb8(255, (x * 255 / size) as u8, (y * 255 / size) as u8, checker)
+        })
+        .collect();
+    let mut texture = Texture::new(size, size, data, 3);
+    texture.sampler.filter = Filter::Trilinear;
+    texture
+}
+
+fn bench_scene(name: &str, mesh: &Mesh, texture: Option<&Texture>, distance: f32, samples: SampleCount) {
+    let camera = Camera {
+        transform: Transform::from_translation(glam::vec3(0.0, 0.0, distance)),
+        ..Default::default()
+    };
+    let model = Mat4::from_rotation_y(0.5);
+    let shader = DefaultShader::new(&model, &(camera.projection() * camera.view() * model), texture);
+    let state = RasterState::default();
+    let mut target = RenderTarget::with_samples(WIDTH, HEIGHT, samples);
+
+    let single = measure(|| {
+        target.clear(0, f32::INFINITY);
+        raster_mesh(mesh, &shader, &mut target, &state);
+        target.resolve();
+    });
+    let tiled = measure(|| {
+        target.clear(0, f32::INFINITY);
+        raster_mesh_tiled(mesh, &shader, &mut target, &state);
+        target.resolve();
+    });
+
+    println!(
+        "{:<8} {}x {:>8} tris  single {:>8.2} ms  tiled {:>8.2} ms",
+        name,
+        samples.count(),
+        mesh.triangles().len(),
+        single.as_secs_f64() * 1000.0,
+        tiled.as_secs_f64() * 1000.0
+    );
+}
+
+fn main() {
+    let helmet = Path::new("Assets/Helmet/DamagedHelmet.gltf");
+    if helmet.exists() {
+        let scene = load_gltf(helmet).expect("helmet");
+        let (mesh, material) = &scene.meshes[0][0];
+        bench_scene("helmet", mesh, material.base_color_texture.as_deref(), 3.0, SampleCount::X1);
+    } else {
+        println!("helmet    not found at {}, drawing the stand-in", helmet.display());
+        let mesh = sphere(STAND_IN_RINGS, STAND_IN_SEGMENTS);
+        let texture = stand_in_texture(STAND_IN_TEXTURE_SIZE);
+        bench_scene("stand-in", &mesh, Some(&texture), 3.0, SampleCount::X1);
+    }
+
+    let sphere = sphere(96, 192);
+    bench_scene("sphere", &sphere, None, 1.6, SampleCount::X1);
+    bench_scene("sphere", &sphere, None, 1.6, SampleCount::X4);
+}
@@ -0,0 +1,203 @@
+//headless renderer, draws a glTF, OBJ, STL or PLY file to an image without opening a window.
+//
+//  render <model.gltf|obj|stl|ply> <output.png|ppm> [options]
+//
+//  --depth <path.png|pgm>   also write the depth buffer as 16 bit grayscale
+//  --size <WxH>             image size, default 480x480
+//  --eye <x,y,z>            camera position, default 0,0,8
+//  --target <x,y,z>         point the camera looks at, default 0,0,0
+//  --fov <degrees>          vertical field of view, default 45
+//  --mode <mode>            std, depth, wireframe, overlay or clipdebug
+//  --texture <path>         albedo texture, replaces the base color textures from the file
+//  --filter <filter>        nearest, linear, bilinear or trilinear texture filtering, default from the file's samplers
+//  --msaa <1|2|4|8>         samples per pixel, default 1
+use glam::{Mat4, Quat, Vec3};
+use std::path::PathBuf;
+use std::process;
+use std::sync::Arc;
+
+use Hello_Triangle::*;
+
+struct Options {
+    model: PathBuf,
+    output: PathBuf,
+    depth: Option<PathBuf>,
+    width: usize,
+    height: usize,
+    eye: Vec3,
+    target: Vec3,
+    fov: f32,
+    render_type: RenderType,
+    texture: Option<PathBuf>,
+    filter: Option<Filter>,
+    samples: SampleCount,
+}
+
+fn main() {
+    let options = parse_args(std::env::args().skip(1)).unwrap_or_else(|e| {
+        eprintln!("{}", e);
+        eprintln!("usage: render <model.gltf|obj|stl|ply> <output.png|ppm> [--depth path] [--size WxH] [--eye x,y,z] [--target x,y,z] [--fov degrees] [--mode std|depth|wireframe|overlay|clipdebug] [--texture path] [--filter nearest|linear|bilinear|trilinear] [--msaa 1|2|4|8]");
+        process::exit(2);
+    });
+
+    let mut scene = load_scene(&options.model).unwrap_or_else(|e| {
+        eprintln!("{}: {}", options.model.display(), e);
+        process::exit(1);
+    });
+    if let Some(path) = &options.texture {
+        let texture = Arc::new(Texture::load(path).unwrap_or_else(|e| {
+            eprintln!("{}: {}", path.display(), e);
+            process::exit(1);
+        }));
+        for (_, material) in scene.meshes.iter_mut().flatten() {
+            material.base_color_texture = Some(texture.clone());
+        }
+    }
+    if let Some(filter) = options.filter {
+        let materials = scene.meshes.iter_mut().flatten().map(|(_, material)| material);
+        for texture in materials.filter_map(|material| material.base_color_texture.as_mut()) {
+            Arc::make_mut(texture).sampler.filter = filter;
+        }
+    }
+
+    let camera = Camera {
+        aspect_ratio: options.width as f32 / options.height as f32,
+        fov: options.fov.to_radians(),
+        transform: look_at(options.eye, options.target),
+        ..Default::default()
+    };
+    let state = RasterState {
+        render_type: options.render_type,
+        ..Default::default()
+    };
+
+    let mut target = RenderTarget::with_samples(options.width, options.height, options.samples);
+    raster_scene(&scene, &Mat4::IDENTITY, &camera, &mut target, &state);
+    target.resolve();
+
+    if let Err(e) = write_color(&options.output, &target.color, options.width, options.height) {
+        eprintln!("failed to write {}: {}", options.output.display(), e);
+        process::exit(1);
+    }
+    if let Some(depth) = &options.depth {
+        if let Err(e) = write_depth(depth, &target.depth, options.width, options.height, target.reverse_z()) {
+            eprintln!("failed to write {}: {}", depth.display(), e);
+            process::exit(1);
+        }
+    }
+}
+
+//camera transform at eye facing target, keeping +Y as up
+fn look_at(eye: Vec3, target: Vec3) -> Transform {
+    let camera_to_world = Mat4::look_at_rh(eye, target, Vec3::Y).inverse();
+    Transform::from_translation_rotation(eye, Quat::from_mat4(&camera_to_world))
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut positional = Vec::new();
+    let mut options = Options {
+        model: PathBuf::new(),
+        output: PathBuf::new(),
+        depth: None,
+        width: 480,
+        height: 480,
+        eye: glam::vec3(0.0, 0.0, 8.0),
+        target: Vec3::ZERO,
+        fov: 45.0,
+        render_type: RenderType::Std,
+        texture: None,
+        filter: None,
+        samples: SampleCount::X1,
+    };
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with("--") {
+            positional.push(arg);
+            continue;
+        }
+        let value = args.next().ok_or_else(|| format!("missing value for {}", arg))?;
+        match arg.as_str() {
+            "--depth" => options.depth = Some(PathBuf::from(value)),
+            "--size" => (options.width, options.height) = parse_size(&value)?,
+            "--eye" => options.eye = parse_vec3(&value)?,
+            "--target" => options.target = parse_vec3(&value)?,
+            "--fov" => options.fov = value.parse().map_err(|_| format!("bad fov {}", value))?,
+            "--mode" => options.render_type = parse_mode(&value)?,
+            "--texture" => options.texture = Some(PathBuf::from(value)),
+            "--filter" => options.filter = Some(parse_filter(&value)?),
+            "--msaa" => options.samples = parse_samples(&value)?,
+            _ => return Err(format!("unknown option {}", arg)),
+        }
+    }
+
+    let [model, output]: [String; 2] = positional
+        .try_into()
+        .map_err(|_| "expected a model and an output path".to_string())?;
+    options.model = PathBuf::from(model);
+    options.output = PathBuf::from(output);
+
+    //catch a bad extension before spending time on the render
+    if !matches!(ImageFormat::from_path(&options.output), Some(ImageFormat::Png | ImageFormat::Ppm)) {
+        return Err(format!("can't write color to {}, use .png or .ppm", options.output.display()));
+    }
+    if let Some(depth) = &options.depth {
+        if !matches!(ImageFormat::from_path(depth), Some(ImageFormat::Png | ImageFormat::Pgm)) {
+            return Err(format!("can't write depth to {}, use .png or .pgm", depth.display()));
+        }
+    }
+
+    Ok(options)
+}
+
+fn parse_size(value: &str) -> Result<(usize, usize), String> {
+    let bad = || format!("bad size {}, expected WxH", value);
+    let (w, h) = value.split_once('x').ok_or_else(bad)?;
+    let (w, h) = (w.parse().map_err(|_| bad())?, h.parse().map_err(|_| bad())?);
+    if w == 0 || h == 0 {
+        return Err(bad());
+    }
+    Ok((w, h))
+}
+
+fn parse_vec3(value: &str) -> Result<Vec3, String> {
+    let components: Vec<f32> = value
+        .split(',')
+        .map(|c| c.trim().parse())
+        .collect::<Result<_, _>>()
+        .map_err(|_| format!("bad vector {}, expected x,y,z", value))?;
+    match components[..] {
+        [x, y, z] => Ok(glam::vec3(x, y, z)),
+        _ => Err(format!("bad vector {}, expected x,y,z", value)),
+    }
+}
+
+fn parse_samples(value: &str) -> Result<SampleCount, String> {
+    match value {
+        "1" => Ok(SampleCount::X1),
+        "2" => Ok(SampleCount::X2),
+        "4" => Ok(SampleCount::X4),
+        "8" => Ok(SampleCount::X8),
+        _ => Err(format!("bad sample count {}, expected 1, 2, 4 or 8", value)),
+    }
+}
+
+fn parse_filter(value: &str) -> Result<Filter, String> {
+    match value {
+        "nearest" => Ok(Filter::Nearest),
+        "linear" => Ok(Filter::Linear),
+        "bilinear" => Ok(Filter::Bilinear),
+        "trilinear" => Ok(Filter::Trilinear),
+        _ => Err(format!("unknown filter {}", value)),
+    }
+}
+
+fn parse_mode(value: &str) -> Result<RenderType, String> {
+    match value {
+        "std" => Ok(RenderType::Std),
+        "depth" => Ok(RenderType::Depth),
+        "wireframe" => Ok(RenderType::Wireframe { overlay: false }),
+        "overlay" => Ok(RenderType::Wireframe { overlay: true }),
+        "clipdebug" => Ok(RenderType::ClipDebug),
+        _ => Err(format!("unknown mode {}", value)),
+    }
+}
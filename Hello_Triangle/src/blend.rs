@@ -0,0 +1,97 @@
+use crate::utils::{from_argb8, to_argb8};
+use glam::Vec4;
+
+//how a fragment gets combined with the color already in the target.
+//colors are rgba in 0..1, alpha is straight except for Premultiplied
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum BlendState {
+    Replace,       //no blending, the fragment overwrites what is there
+    Alpha,         //src * a + dst * (1 - a)
+    Additive,      //src * a + dst
+    Premultiplied, //src + dst * (1 - a), src already carries its alpha
+    Multiply,      //src * dst
+}
+
+impl BlendState {
+    #[inline]
+    pub fn blend(self, src: Vec4, dst: Vec4) -> Vec4 {
+        let a = src.w;
+        let rgb = match self {
+            BlendState::Replace => return src,
+            BlendState::Alpha => src.truncate() * a + dst.truncate() * (1.0 - a),
+            BlendState::Additive => src.truncate() * a + dst.truncate(),
+            BlendState::Premultiplied => src.truncate() + dst.truncate() * (1.0 - a),
+            BlendState::Multiply => src.truncate() * dst.truncate(),
+        };
+        //coverage builds up the same way for every mode
+        rgb.extend(a + dst.w * (1.0 - a))
+    }
+
+    //blends src over the packed color dst, Replace doesn't look at dst at all
+    #[inline]
+    pub fn apply(self, src: Vec4, dst: u32) -> u32 {
+        match self {
+            BlendState::Replace => rgba_to_argb8(src),
+            _ => rgba_to_argb8(self.blend(src, argb8_to_rgba(dst))),
+        }
+    }
+}
+
+//glTF material alphaMode, says how the alpha a fragment shader returns is used
+#[derive(Debug, Default, Copy, Clone, PartialEq)]
+pub enum AlphaMode {
+    #[default]
+    Opaque,               //alpha is ignored
+    Mask { cutoff: f32 }, //pixels with alpha below cutoff are discarded, the rest are opaque
+    Blend,                //alpha blended in the transparent pass, see raster_meshes
+}
+
+impl AlphaMode {
+    pub fn from_gltf(material: &gltf::Material) -> Self {
+        match material.alpha_mode() {
+            gltf::material::AlphaMode::Opaque => AlphaMode::Opaque,
+            //0.5 is the default cutoff in the glTF spec
+            gltf::material::AlphaMode::Mask => AlphaMode::Mask {
+                cutoff: material.alpha_cutoff().unwrap_or(0.5),
+            },
+            gltf::material::AlphaMode::Blend => AlphaMode::Blend,
+        }
+    }
+
+    //alpha a pixel ends up with, None if it gets discarded
+    #[inline]
+    pub fn apply(self, alpha: f32) -> Option<f32> {
+        match self {
+            AlphaMode::Opaque => Some(1.0),
+            AlphaMode::Mask { cutoff } => (alpha >= cutoff).then_some(1.0),
+            AlphaMode::Blend => Some(alpha),
+        }
+    }
+
+    //the more transparent mode of the two, for meshes merged out of several materials
+    pub fn max(self, other: AlphaMode) -> AlphaMode {
+        match (self, other) {
+            (AlphaMode::Blend, _) | (_, AlphaMode::Blend) => AlphaMode::Blend,
+            (AlphaMode::Mask { cutoff: a }, AlphaMode::Mask { cutoff: b }) => AlphaMode::Mask { cutoff: a.max(b) },
+            (AlphaMode::Mask { cutoff }, _) | (_, AlphaMode::Mask { cutoff }) => AlphaMode::Mask { cutoff },
+            _ => AlphaMode::Opaque,
+        }
+    }
+}
+
+//values outside 0..1 saturate
+#[inline]
+pub fn rgba_to_argb8(color: Vec4) -> u32 {
+    to_argb8(
+        (color.w * 255.0) as u8,
+        (color.x * 255.0) as u8,
+        (color.y * 255.0) as u8,
+        (color.z * 255.0) as u8,
+    )
+}
+
+#[inline]
+pub fn argb8_to_rgba(argb: u32) -> Vec4 {
+    let (a, r, g, b) = from_argb8(argb);
+    glam::vec4(r as f32, g as f32, b as f32, a as f32) / 255.0
+}
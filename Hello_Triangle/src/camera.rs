@@ -9,6 +9,9 @@ pub struct Camera {
     pub aspect_ratio: f32,
     pub transform: Transform,
     pub speed: f32,
+    //depth goes from 1 at the near plane to 0 at the far plane, which spreads float precision much more
+    //evenly over the distance. Needs DepthState::reverse_z() and a RenderTarget made with_reverse_z
+    pub reverse_z: bool,
 }
 
 impl Default for Camera {
@@ -20,18 +23,20 @@ impl Default for Camera {
             aspect_ratio: 1.0,
             transform: Transform::IDENTITY,
             speed: 25.0,
+            reverse_z: false,
         }
     }
 }
 
 impl Camera {
     pub fn projection(&self) -> Mat4 {
-        Mat4::perspective_rh(
-            self.fov,
-            self.aspect_ratio,
-            self.frustum_near,
-            self.frustum_far,
-        )
+        //swapping the planes flips the depth range, the clip planes stay at 0 <= z <= w
+        let (near, far) = if self.reverse_z {
+            (self.frustum_far, self.frustum_near)
+        } else {
+            (self.frustum_near, self.frustum_far)
+        };
+        Mat4::perspective_rh(self.fov, self.aspect_ratio, near, far)
     }
 
     pub fn view(&self) -> Mat4 {
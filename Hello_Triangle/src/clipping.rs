@@ -0,0 +1,165 @@
+use crate::geometry::{Triangle, Varying};
+use crate::utils::Lerp;
+use glam::Vec4;
+
+//how far out the side planes sit, in multiples of the frustum. Triangles poking out less than this
+//aren't clipped on x/y, the screen bounding box clamp takes care of them
+pub const DEFAULT_GUARD_BAND: f32 = 2.0;
+
+//every plane can add at most one vertex to a triangle
+const MAX_CLIPPED_VERTICES: usize = 3 + 6;
+
+pub enum ClipResult<V = crate::geometry::Vertex> {
+    None,
+    One(Triangle<V>),      //fully inside, passed through untouched
+    Fan(Vec<Triangle<V>>), //clipped polygon split up into a triangle fan
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CullMode {
+    None,
+    Back,
+    Front,
+}
+
+//which winding counts as the front of a triangle. glTF uses counter clockwise
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum FrontFace {
+    CounterClockwise,
+    Clockwise,
+}
+
+//per frame counters, printed by the viewer in RenderType::ClipDebug
+#[derive(Debug, Default, Copy, Clone)]
+pub struct ClipStats {
+    pub culled: usize,  //backface or outside the view frustum
+    pub clipped: usize, //crossed the near or far plane, or the guard band
+}
+
+impl std::ops::AddAssign for ClipStats {
+    fn add_assign(&mut self, rhs: Self) {
+        self.culled += rhs.culled;
+        self.clipped += rhs.clipped;
+    }
+}
+
+//homogeneous clip planes, a position is inside when dot(plane, position) >= 0.
+//near comes first so the other planes never see vertices behind the camera
+fn clip_planes(guard_band: f32) -> [Vec4; 6] {
+    [
+        glam::vec4(0.0, 0.0, 1.0, 0.0),         //near, z >= 0
+        glam::vec4(0.0, 0.0, -1.0, 1.0),        //far, z <= w
+        glam::vec4(1.0, 0.0, 0.0, guard_band),  //left, x >= -w * guard_band
+        glam::vec4(-1.0, 0.0, 0.0, guard_band), //right
+        glam::vec4(0.0, 1.0, 0.0, guard_band),  //bottom
+        glam::vec4(0.0, -1.0, 0.0, guard_band), //top
+    ]
+}
+
+//View Frustum Culling
+pub fn cull_triangle_view_frustum<V: Varying>(tri: &Triangle<V>) -> bool {
+    let (p0, p1, p2) = (
+        tri.vert0.position(),
+        tri.vert1.position(),
+        tri.vert2.position(),
+    );
+
+    // cull tests against the 6 planes
+    if p0.x > p0.w && p1.x > p1.w && p2.x > p2.w {
+        return true;
+    }
+    if p0.x < -p0.w && p1.x < -p1.w && p2.x < -p2.w {
+        return true;
+    }
+    if p0.y > p0.w && p1.y > p1.w && p2.y > p2.w {
+        return true;
+    }
+    if p0.y < -p0.w && p1.y < -p1.w && p2.y < -p2.w {
+        return true;
+    }
+    if p0.z > p0.w && p1.z > p1.w && p2.z > p2.w {
+        return true;
+    }
+    if p0.z < 0.0 && p1.z < 0.0 && p2.z < 0.0 {
+        return true;
+    }
+
+    false
+}
+
+//area is the signed screen space area from edge_function, positive when the triangle winds counter clockwise in NDC
+pub fn cull_triangle_backface(area: f32, cull_mode: CullMode, front_face: FrontFace) -> bool {
+    //degenerate, there is nothing to draw either way
+    if area == 0.0 || area.is_nan() {
+        return true;
+    }
+
+    let front = match front_face {
+        FrontFace::CounterClockwise => area > 0.0,
+        FrontFace::Clockwise => area < 0.0,
+    };
+
+    match cull_mode {
+        CullMode::None => false,
+        CullMode::Back => !front,
+        CullMode::Front => front,
+    }
+}
+
+//Sutherland-Hodgman, clips the polygon against one plane after the other
+fn clip_polygon<V: Varying>(vertices: &[V], planes: &[Vec4]) -> Vec<V> {
+    let mut output = Vec::with_capacity(MAX_CLIPPED_VERTICES);
+    output.extend_from_slice(vertices);
+
+    for plane in planes {
+        let input = std::mem::replace(&mut output, Vec::with_capacity(MAX_CLIPPED_VERTICES));
+
+        for (i, current) in input.iter().enumerate() {
+            let next = input[(i + 1) % input.len()];
+            let d0 = plane.dot(current.position());
+            let d1 = plane.dot(next.position());
+
+            if d0 >= 0.0 {
+                output.push(*current);
+            }
+            //edge crosses the plane, keep the intersection
+            if (d0 >= 0.0) != (d1 >= 0.0) {
+                output.push(Lerp(*current, next, d0 / (d0 - d1)));
+            }
+        }
+
+        if output.is_empty() {
+            break;
+        }
+    }
+
+    output
+}
+
+//backfaces are left in, winding can only be judged in screen space after clipping. See cull_triangle_backface
+pub fn clip_cull_triangle<V: Varying>(tri: &Triangle<V>, guard_band: f32) -> ClipResult<V> {
+    if cull_triangle_view_frustum(tri) {
+        return ClipResult::None;
+    }
+
+    let planes = clip_planes(guard_band);
+    let vertices = [tri.vert0, tri.vert1, tri.vert2];
+
+    let inside = planes
+        .iter()
+        .all(|plane| vertices.iter().all(|v| plane.dot(v.position()) >= 0.0));
+    if inside {
+        return ClipResult::One(*tri);
+    }
+
+    let polygon = clip_polygon(&vertices, &planes);
+    if polygon.len() < 3 {
+        ClipResult::None
+    } else {
+        ClipResult::Fan(
+            (1..polygon.len() - 1)
+                .map(|i| Triangle::Construct(polygon[0], polygon[i], polygon[i + 1]))
+                .collect(),
+        )
+    }
+}
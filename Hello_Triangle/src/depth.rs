@@ -0,0 +1,66 @@
+//passes when the incoming value compares this way against the one already stored
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum CompareFunction {
+    Never,
+    Less,
+    LessEqual,
+    Equal,
+    GreaterEqual,
+    Greater,
+    NotEqual,
+    Always,
+}
+
+impl CompareFunction {
+    #[inline]
+    pub fn test<T: PartialOrd>(self, value: T, stored: T) -> bool {
+        match self {
+            CompareFunction::Never => false,
+            CompareFunction::Less => value < stored,
+            CompareFunction::LessEqual => value <= stored,
+            CompareFunction::Equal => value == stored,
+            CompareFunction::GreaterEqual => value >= stored,
+            CompareFunction::Greater => value > stored,
+            CompareFunction::NotEqual => value != stored,
+            CompareFunction::Always => true,
+        }
+    }
+
+    //same test, but values within bias of stored count as in front. For lines lying on the surface they outline
+    #[inline]
+    pub fn test_biased(self, value: f32, stored: f32, bias: f32) -> bool {
+        match self {
+            CompareFunction::Less | CompareFunction::LessEqual => value <= stored + bias,
+            CompareFunction::Greater | CompareFunction::GreaterEqual => value >= stored - bias,
+            _ => self.test(value, stored),
+        }
+    }
+}
+
+//depth test and whether passing pixels write their depth.
+//decals want LessEqual, skyboxes Equal against a cleared far value or Always without writes,
+//a depth pre-pass writes with Less and the shading pass after it uses Equal without writing
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct DepthState {
+    pub compare: CompareFunction,
+    pub write: bool,
+}
+
+impl DepthState {
+    //for Camera::reverse_z, near is 1 and far is 0 there so closer means greater
+    pub fn reverse_z() -> Self {
+        Self {
+            compare: CompareFunction::Greater,
+            write: true,
+        }
+    }
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            compare: CompareFunction::Less,
+            write: true,
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use glam::{Vec2, Vec3};
+
+//screen positions get snapped to 1/256th of a pixel before edge setup. Edge functions are then
+//evaluated exactly in integers, so a pixel center lying on an edge shared by two triangles is
+//always decided the same way for both of them
+pub const SUBPIXEL_BITS: u32 = 8;
+pub const SUBPIXEL_SCALE: f32 = (1 << SUBPIXEL_BITS) as f32;
+
+//position on the sub pixel grid
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct FixedPoint {
+    pub x: i64,
+    pub y: i64,
+}
+
+impl FixedPoint {
+    pub fn from_screen(p: Vec2) -> Self {
+        Self {
+            x: (p.x * SUBPIXEL_SCALE).round() as i64,
+            y: (p.y * SUBPIXEL_SCALE).round() as i64,
+        }
+    }
+
+    //center of pixel x, y
+    pub fn pixel_center(x: usize, y: usize) -> Self {
+        let half = 1 << (SUBPIXEL_BITS - 1);
+        Self {
+            x: ((x as i64) << SUBPIXEL_BITS) + half,
+            y: ((y as i64) << SUBPIXEL_BITS) + half,
+        }
+    }
+
+    pub fn to_screen(self) -> Vec2 {
+        glam::vec2(self.x as f32, self.y as f32) / SUBPIXEL_SCALE
+    }
+}
+
+//same as utils::edge_function, just exact
+pub fn edge_function_fixed(v0: FixedPoint, v1: FixedPoint, p: FixedPoint) -> i64 {
+    (p.x - v0.x) * (v1.y - v0.y) - (p.y - v0.y) * (v1.x - v0.x)
+}
+
+//sample positions relative to the pixel center in 1/16 pixel, the standard D3D/Vulkan patterns
+pub fn sample_offsets(samples: usize) -> &'static [(i64, i64)] {
+    match samples {
+        1 => &[(0, 0)],
+        2 => &[(4, 4), (-4, -4)],
+        4 => &[(-2, -6), (6, -2), (-6, 2), (2, 6)],
+        8 => &[(1, -3), (-1, 3), (5, 1), (-3, -5), (-5, 5), (-7, -1), (3, 7), (7, -7)],
+        _ => panic!("unsupported sample count {}", samples),
+    }
+}
+
+//integer edge functions of a screen triangle.
+//the winding is normalized away so covered pixels always have all three weights positive, either
+//winding gets drawn and culling is left to cull_triangle_backface.
+//edge functions are linear, so after evaluating them once at the first pixel the rasterizer only
+//has to add step_x per pixel and step_y per row
+#[derive(Debug, Copy, Clone)]
+pub struct EdgeSetup {
+    pub vertices: [FixedPoint; 3],
+    pub area: i64, //twice the area in sub pixel units, never negative
+    pub step_x: [i64; 3], //change of the weights one pixel to the right
+    pub step_y: [i64; 3], //change of the weights one pixel down
+    inv_area: f32,
+    flip: bool,
+    //top-left fill rule: a pixel exactly on an edge only belongs to the triangle if that edge is a top or left edge.
+    //0 for those, -1 for the rest so a weight of 0 fails the >= 0 test
+    bias: [i64; 3],
+}
+
+impl EdgeSetup {
+    pub fn new(screen: &[Vec2; 3]) -> Self {
+        let vertices = screen.map(FixedPoint::from_screen);
+        let area = edge_function_fixed(vertices[0], vertices[1], vertices[2]);
+        let flip = area < 0;
+
+        //weight i belongs to the edge opposite of vertex i
+        let bias = [(1, 2), (2, 0), (0, 1)].map(|(a, b)| {
+            let (a, b) = if flip { (vertices[b], vertices[a]) } else { (vertices[a], vertices[b]) };
+            let (dx, dy) = (b.x - a.x, b.y - a.y);
+            //y points down on screen, with this winding left edges go down and top edges go left
+            let top = dy == 0 && dx < 0;
+            let left = dy > 0;
+            if top || left {
+                0
+            } else {
+                -1
+            }
+        });
+
+        //edge from a to b: d/dx = b.y - a.y, d/dy = a.x - b.x, in sub pixel units
+        let sign = if flip { -1 } else { 1 };
+        let edges = [(1, 2), (2, 0), (0, 1)];
+        let step_x = edges.map(|(a, b)| (sign * (vertices[b].y - vertices[a].y)) << SUBPIXEL_BITS);
+        let step_y = edges.map(|(a, b)| (sign * (vertices[a].x - vertices[b].x)) << SUBPIXEL_BITS);
+
+        Self {
+            vertices,
+            area: area.abs(),
+            step_x,
+            step_y,
+            inv_area: 1.0 / area.abs() as f32,
+            flip,
+            bias,
+        }
+    }
+
+    //unnormalized barycentric weights at p, positive inside the triangle
+    pub fn weights(&self, p: FixedPoint) -> [i64; 3] {
+        let [v0, v1, v2] = self.vertices;
+        let weights = [
+            edge_function_fixed(p, v1, v2),
+            edge_function_fixed(p, v2, v0),
+            edge_function_fixed(p, v0, v1),
+        ];
+        if self.flip {
+            weights.map(|w| -w)
+        } else {
+            weights
+        }
+    }
+
+    pub fn covers(&self, weights: &[i64; 3]) -> bool {
+        self.area > 0 && (0..3).all(|i| weights[i] + self.bias[i] >= 0)
+    }
+
+    //weights at a sample offset from the pixel the weights were evaluated at, offset in 1/16 pixel.
+    //exact, the per pixel steps are whole multiples of 256
+    pub fn sample_weights(&self, weights: &[i64; 3], offset: (i64, i64)) -> [i64; 3] {
+        let (ox, oy) = offset;
+        [0, 1, 2].map(|i| weights[i] + (self.step_x[i] * ox + self.step_y[i] * oy) / 16)
+    }
+
+    pub fn step(weights: &[i64; 3], step: &[i64; 3]) -> [i64; 3] {
+        [weights[0] + step[0], weights[1] + step[1], weights[2] + step[2]]
+    }
+
+    //coverage of the 4 pixels starting at weights and going right, bit k set if pixel k is covered
+    pub fn coverage4(&self, weights: &[i64; 3]) -> u32 {
+        if self.area == 0 {
+            return 0;
+        }
+
+        let mut mask = 0;
+        let mut lane = *weights;
+        for k in 0..4 {
+            if self.covers(&lane) {
+                mask |= 1 << k;
+            }
+            lane = Self::step(&lane, &self.step_x);
+        }
+        mask
+    }
+
+    //weights have to be covered ones, see covers
+    pub fn to_barycentric(&self, weights: &[i64; 3]) -> Vec3 {
+        glam::vec3(weights[0] as f32, weights[1] as f32, weights[2] as f32) * self.inv_area
+    }
+
+    //barycentric coordinates of the center of pixel x, y if the fill rule gives that pixel to this triangle
+    pub fn barycentric(&self, x: usize, y: usize) -> Option<Vec3> {
+        self.barycentric_at(FixedPoint::pixel_center(x, y))
+    }
+
+    pub fn barycentric_at(&self, p: FixedPoint) -> Option<Vec3> {
+        let weights = self.weights(p);
+        self.covers(&weights).then(|| self.to_barycentric(&weights))
+    }
+}
@@ -0,0 +1,62 @@
+use crate::texture::TextureError;
+use std::fmt;
+use std::io;
+
+//everything loading assets can run into. Messages are meant to be shown to whoever picked the file
+#[derive(Debug)]
+pub enum Error {
+    Io(io::Error),                  //missing or unreadable file
+    Gltf(gltf::Error),              //not valid glTF, or one of its buffers or images is broken
+    Texture(TextureError),
+    MissingAttribute(&'static str), //vertex attribute a primitive can't do without, like POSITION
+    Unsupported(String),            //valid data the renderer can't draw, like line primitives
+    Invalid(String),                //data that contradicts itself, like indices past the end of the vertices
+}
+
+pub type Result<T> = std::result::Result<T, Error>;
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Error::Io(e) => write!(f, "couldn't read file: {}", e),
+            Error::Gltf(e) => write!(f, "couldn't load glTF: {}", e),
+            Error::Texture(e) => e.fmt(f),
+            Error::MissingAttribute(attribute) => write!(f, "mesh has no {} attribute", attribute),
+            Error::Unsupported(what) => write!(f, "unsupported {}", what),
+            Error::Invalid(what) => write!(f, "invalid mesh data: {}", what),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(e) => Some(e),
+            Error::Gltf(e) => Some(e),
+            Error::Texture(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io(e)
+    }
+}
+
+//a missing .gltf or .bin is a missing file like any other
+impl From<gltf::Error> for Error {
+    fn from(e: gltf::Error) -> Self {
+        match e {
+            gltf::Error::Io(e) => Error::Io(e),
+            e => Error::Gltf(e),
+        }
+    }
+}
+
+impl From<TextureError> for Error {
+    fn from(e: TextureError) -> Self {
+        Error::Texture(e)
+    }
+}
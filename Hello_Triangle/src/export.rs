@@ -0,0 +1,108 @@
+use crate::utils::from_argb8;
+use std::fs::File;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+//what to encode an image as, picked from the file extension
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Ppm, //binary netpbm color, only for write_color
+    Pgm, //binary netpbm grayscale, only for write_depth
+}
+
+impl ImageFormat {
+    pub fn from_path(path: &Path) -> Option<Self> {
+        let extension = path.extension()?.to_str()?.to_ascii_lowercase();
+        match extension.as_str() {
+            "png" => Some(Self::Png),
+            "ppm" => Some(Self::Ppm),
+            "pgm" => Some(Self::Pgm),
+            _ => None,
+        }
+    }
+}
+
+//ndc depth as a 0..1 gray, near black and far white whichever way the target stores it.
+//reverse_z is the target's, see RenderTarget::with_reverse_z. Cleared pixels (infinitely far) end up white
+pub fn display_depth(depth: f32, reverse_z: bool) -> f32 {
+    let depth = if reverse_z { 1.0 - depth } else { depth };
+    depth.clamp(0.0, 1.0)
+}
+
+pub fn depth_to_u16(depth: f32, reverse_z: bool) -> u16 {
+    (display_depth(depth, reverse_z) * u16::MAX as f32).round() as u16
+}
+
+//writes an argb8 framebuffer, alpha is dropped
+pub fn write_color(path: &Path, buffer: &[u32], width: usize, height: usize) -> io::Result<()> {
+    assert_eq!(buffer.len(), width * height);
+    let rgb: Vec<u8> = buffer
+        .iter()
+        .flat_map(|&argb| {
+            let (_, r, g, b) = from_argb8(argb);
+            [r, g, b]
+        })
+        .collect();
+
+    match format_of(path)? {
+        ImageFormat::Png => write_png(path, &rgb, width, height, png::ColorType::Rgb, png::BitDepth::Eight),
+        ImageFormat::Ppm => write_netpbm(path, "P6", 255, &rgb, width, height),
+        ImageFormat::Pgm => Err(wrong_format(path, "color", ".png or .ppm")),
+    }
+}
+
+//writes a depth buffer as 16 bit grayscale, see display_depth
+pub fn write_depth(path: &Path, z_buffer: &[f32], width: usize, height: usize, reverse_z: bool) -> io::Result<()> {
+    assert_eq!(z_buffer.len(), width * height);
+    //both formats want 16 bit samples big endian
+    let gray: Vec<u8> = z_buffer
+        .iter()
+        .flat_map(|&depth| depth_to_u16(depth, reverse_z).to_be_bytes())
+        .collect();
+
+    match format_of(path)? {
+        ImageFormat::Png => write_png(path, &gray, width, height, png::ColorType::Grayscale, png::BitDepth::Sixteen),
+        ImageFormat::Pgm => write_netpbm(path, "P5", u16::MAX as u32, &gray, width, height),
+        ImageFormat::Ppm => Err(wrong_format(path, "depth", ".png or .pgm")),
+    }
+}
+
+fn wrong_format(path: &Path, what: &str, formats: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidInput,
+        format!("can't write {} to {}, use {}", what, path.display(), formats),
+    )
+}
+
+fn format_of(path: &Path) -> io::Result<ImageFormat> {
+    ImageFormat::from_path(path).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("unknown image format for {}, use .png, .ppm or .pgm", path.display()),
+        )
+    })
+}
+
+fn write_png(
+    path: &Path,
+    data: &[u8],
+    width: usize,
+    height: usize,
+    color_type: png::ColorType,
+    bit_depth: png::BitDepth,
+) -> io::Result<()> {
+    let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width as u32, height as u32);
+    encoder.set_color(color_type);
+    encoder.set_depth(bit_depth);
+    let mut writer = encoder.write_header().map_err(io::Error::other)?;
+    writer.write_image_data(data).map_err(io::Error::other)?;
+    writer.finish().map_err(io::Error::other)
+}
+
+fn write_netpbm(path: &Path, magic: &str, max_value: u32, data: &[u8], width: usize, height: usize) -> io::Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    write!(file, "{}\n{} {}\n{}\n", magic, width, height, max_value)?;
+    file.write_all(data)?;
+    file.flush()
+}
@@ -1,6 +1,14 @@
+use crate::blend::AlphaMode;
+use crate::edge::{EdgeSetup, FixedPoint};
+use crate::error::{Error, Result};
+use crate::material::Material;
+use crate::render_target::Rect;
+use crate::utils::{edge_function, map_to_range};
 use glam::{Mat4, UVec3, Vec2, Vec3, Vec4, Vec4Swizzles};
-use std::ops::{Add, AddAssign, Mul, MulAssign, Sub};
+use std::collections::HashMap;
+use std::ops::{Add, AddAssign, Mul, MulAssign, Range, Sub};
 
+#[derive(Debug, Copy, Clone)]
 pub struct BoundingBox2D {
     pub left: f32,
     pub right: f32,
@@ -22,11 +30,39 @@ pub fn get_triangle_bounding_box_2d(tri: &[Vec2; 3]) -> BoundingBox2D {
     }
 }
 
+impl BoundingBox2D {
+    //overlapping area of two boxes, None if they don't touch
+    pub fn intersect(&self, other: &BoundingBox2D) -> Option<BoundingBox2D> {
+        let left = self.left.max(other.left);
+        let right = self.right.min(other.right);
+        let top = self.top.max(other.top);
+        let bot = self.bot.min(other.bot);
+
+        if left > right || top > bot {
+            None
+        } else {
+            Some(BoundingBox2D {
+                left,
+                right,
+                top,
+                bot,
+            })
+        }
+    }
+}
+
 pub struct Point {
     pub x: i32,
     pub y: i32,
 }
 
+//anything the pipeline can clip and interpolate across a triangle. Vertex is the built in one,
+//shaders can bring their own struct with tangents, world positions, extra uv sets and so on
+pub trait Varying: Copy + Add<Output = Self> + Sub<Output = Self> + Mul<f32, Output = Self> {
+    //clip space position, needed for clipping and the perspective divide
+    fn position(&self) -> Vec4;
+}
+
 //data struct
 #[derive(Debug, Copy, Clone)]
 pub struct Vertex {
@@ -49,6 +85,12 @@ impl Vertex {
     }
 }
 
+impl Varying for Vertex {
+    fn position(&self) -> Vec4 {
+        self.position
+    }
+}
+
 impl Default for Vertex {
     fn default() -> Self {
         Self {
@@ -106,10 +148,20 @@ impl MulAssign<f32> for Vertex {
     }
 }
 
+//a run of triangles added in one go, like a glTF primitive
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub triangles: Range<usize>,  //into Mesh::triangles
+    pub material: Option<usize>, //index of the glTF material, None for the default one
+}
+
 #[derive(Debug, Clone)]
 pub struct Mesh {
     triangles: Vec<UVec3>,
     vertices: Vec<Vertex>,
+    sections: Vec<Section>,
+    double_sided: bool, //never backface culled
+    alpha_mode: AlphaMode,
 }
 
 impl Mesh {
@@ -117,6 +169,9 @@ impl Mesh {
         Self {
             triangles: Vec::new(),
             vertices: Vec::new(),
+            sections: Vec::new(),
+            double_sided: false,
+            alpha_mode: AlphaMode::Opaque,
         }
     }
 
@@ -129,6 +184,36 @@ impl Mesh {
         &self.vertices //vertex buffer
     }
 
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    pub fn double_sided(&self) -> bool {
+        self.double_sided
+    }
+
+    pub fn set_double_sided(&mut self, double_sided: bool) {
+        self.double_sided = double_sided;
+    }
+
+    pub fn alpha_mode(&self) -> AlphaMode {
+        self.alpha_mode
+    }
+
+    pub fn set_alpha_mode(&mut self, alpha_mode: AlphaMode) {
+        self.alpha_mode = alpha_mode;
+    }
+
+    //center of the bounding box in model space, what the transparent pass sorts by
+    pub fn center(&self) -> Vec3 {
+        let mut positions = self.vertices.iter().map(|v| v.position.truncate());
+        let Some(first) = positions.next() else {
+            return Vec3::ZERO;
+        };
+        let (min, max) = positions.fold((first, first), |(min, max), p| (min.min(p), max.max(p)));
+        (min + max) * 0.5
+    }
+
     pub fn get_vertices_from_triangle(&self, triangle: UVec3) -> [&Vertex; 3] {
         [
             &self.vertices[triangle.x as usize],
@@ -144,12 +229,52 @@ impl Mesh {
     }
 
     pub fn add_section_from_vertices(&mut self, triangles: &[UVec3], vertices: &[Vertex]) {
-        let offset = self.vertices.len() as u32;
-        let triangles: Vec<UVec3> = triangles.iter().map(|tri| *tri + offset).collect(); //rust closure, iter map
-        self.triangles.extend_from_slice(&triangles);
+        self.add_material_section_from_vertices(triangles, vertices, None);
+    }
+
+    pub fn add_material_section_from_vertices(&mut self, triangles: &[UVec3], vertices: &[Vertex], material: Option<usize>) {
+        self.push_section(triangles, material);
         self.vertices.extend_from_slice(vertices);
     }
 
+    //every section as a mesh of its own with just the vertices it uses
+    pub fn split_sections(&self) -> Vec<Mesh> {
+        self.sections
+            .iter()
+            .map(|section| {
+                let mut remap: HashMap<u32, u32> = HashMap::new();
+                let mut vertices = Vec::new();
+                let triangles: Vec<UVec3> = self.triangles[section.triangles.clone()]
+                    .iter()
+                    .map(|tri| {
+                        UVec3::from(tri.to_array().map(|id| {
+                            *remap.entry(id).or_insert_with(|| {
+                                vertices.push(self.vertices[id as usize]);
+                                vertices.len() as u32 - 1
+                            })
+                        }))
+                    })
+                    .collect();
+                let mut mesh = Mesh::new();
+                mesh.add_material_section_from_vertices(&triangles, &vertices, section.material);
+                mesh.double_sided = self.double_sided;
+                mesh.alpha_mode = self.alpha_mode;
+                mesh
+            })
+            .collect()
+    }
+
+    //appends triangles indexing vertices that are about to be added after the existing ones
+    fn push_section(&mut self, triangles: &[UVec3], material: Option<usize>) {
+        let offset = self.vertices.len() as u32;
+        let start = self.triangles.len();
+        self.triangles.extend(triangles.iter().map(|tri| *tri + offset)); //rust closure, iter map
+        self.sections.push(Section {
+            triangles: start..self.triangles.len(),
+            material,
+        });
+    }
+
     pub fn add_section_from_buffers(
         &mut self,
         triangles: &[UVec3],
@@ -158,15 +283,29 @@ impl Mesh {
         colors: &[Vec3],
         uvs: &[Vec2],
     ) {
-        self.triangles.extend_from_slice(triangles);
+        self.add_material_section_from_buffers(triangles, positions, normals, colors, uvs, None);
+    }
 
+    pub fn add_material_section_from_buffers(
+        &mut self,
+        triangles: &[UVec3],
+        positions: &[Vec3],
+        normals: &[Vec3],
+        colors: &[Vec3],
+        uvs: &[Vec2],
+        material: Option<usize>,
+    ) {
+        self.push_section(triangles, material);
+
+        //empty attribute buffers get defaults, zero normals only get the ambient light
+        let has_normals = !normals.is_empty();
         let has_uvs = !uvs.is_empty();
         let has_colors = !colors.is_empty();
 
         for i in 0..positions.len() {
             let vertex = Vertex::Construct(
                 positions[i].extend(1.0),
-                normals[i],
+                if has_normals { normals[i] } else { Vec3::ZERO },
                 if has_colors { colors[i] } else { Vec3::ONE },
                 if has_uvs { uvs[i] } else { Vec2::ZERO },
             );
@@ -174,47 +313,138 @@ impl Mesh {
         }
     }
 
-    pub fn load_from_gltf(mesh: &gltf::Mesh, buffers: &[gltf::buffer::Data]) -> Mesh {
-        let mut positions: Vec<Vec3> = Vec::new();
-        let mut tex_coords: Vec<Vec2> = Vec::new();
-        let mut normals: Vec<Vec3> = Vec::new();
-        let mut indices = vec![];
-        // TODO: handle errors
+    //every primitive of the mesh as its own section, keeping the index of its material
+    pub fn load_from_gltf(mesh: &gltf::Mesh, buffers: &[gltf::buffer::Data]) -> Result<Mesh> {
         let mut result = Mesh::new();
         for primitive in mesh.primitives() {
-            let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
-            if let Some(indices_reader) = reader.read_indices() {
-                indices_reader.into_u32().for_each(|i| indices.push(i));
-            }
-            if let Some(positions_reader) = reader.read_positions() {
-                positions_reader.for_each(|p| positions.push(Vec3::new(p[0], p[1], p[2])));
-            }
-            if let Some(normals_reader) = reader.read_normals() {
-                normals_reader.for_each(|n| normals.push(Vec3::new(n[0], n[1], n[2])));
-            }
-            if let Some(tex_coord_reader) = reader.read_tex_coords(0) {
-                tex_coord_reader
-                    .into_f32()
-                    .for_each(|tc| tex_coords.push(Vec2::new(tc[0], tc[1])));
+            let material = primitive.material();
+            //culling is per mesh, so one double sided material is enough to turn it off
+            if material.double_sided() {
+                result.double_sided = true;
             }
+            //same for blending, one transparent material puts the whole mesh in the transparent pass
+            result.alpha_mode = result.alpha_mode.max(AlphaMode::from_gltf(&material));
+
+            let tex_coord = material
+                .pbr_metallic_roughness()
+                .base_color_texture()
+                .map_or(0, |info| info.tex_coord());
+            result.add_primitive(&primitive, buffers, tex_coord, material.index())?;
+        }
+        Ok(result)
+    }
+
+    //one primitive on its own, uvs come from the texcoord set the material's base color texture uses
+    pub fn load_primitive_from_gltf(
+        primitive: &gltf::Primitive,
+        buffers: &[gltf::buffer::Data],
+        material: &Material,
+    ) -> Result<Mesh> {
+        let mut result = Mesh::new();
+        result.add_primitive(primitive, buffers, material.tex_coord, primitive.material().index())?;
+        result.double_sided = material.double_sided;
+        result.alpha_mode = material.alpha_mode;
+        Ok(result)
+    }
 
-            let colors: Vec<Vec3> = positions.iter().map(|_| Vec3::ONE).collect();
-            println!("Num indices: {:?}", indices.len());
-            println!("tex_coords: {:?}", tex_coords.len());
-            println!("positions: {:?}", positions.len());
+    fn add_primitive(
+        &mut self,
+        primitive: &gltf::Primitive,
+        buffers: &[gltf::buffer::Data],
+        tex_coord: u32,
+        material: Option<usize>,
+    ) -> Result<()> {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            return Err(Error::Unsupported(format!("{:?} primitives", primitive.mode())));
+        }
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+        let positions: Vec<Vec3> = reader
+            .read_positions()
+            .ok_or(Error::MissingAttribute("POSITION"))?
+            .map(Vec3::from)
+            .collect();
+        let normals: Vec<Vec3> = reader
+            .read_normals()
+            .map_or_else(Vec::new, |normals| normals.map(Vec3::from).collect());
+        let tex_coords: Vec<Vec2> = reader
+            .read_tex_coords(tex_coord)
+            .map_or_else(Vec::new, |uvs| uvs.into_f32().map(Vec2::from).collect());
+        //unindexed primitives draw their vertices in order
+        let indices: Vec<u32> = reader
+            .read_indices()
+            .map_or_else(|| (0..positions.len() as u32).collect(), |indices| indices.into_u32().collect());
+        if let Some(index) = indices.iter().find(|&&index| index as usize >= positions.len()) {
+            return Err(Error::Invalid(format!("index {} with {} vertices", index, positions.len())));
+        }
+        let matches = |count: usize| count == 0 || count == positions.len();
+        if !matches(normals.len()) || !matches(tex_coords.len()) {
+            return Err(Error::Invalid("attributes with different vertex counts".to_string()));
+        }
+        let triangles: Vec<UVec3> = indices.chunks_exact(3).map(UVec3::from_slice).collect();
 
-            let triangles: Vec<UVec3> = indices
+        //without normals the spec asks for flat shading, which takes three vertices of its own per triangle
+        let (triangles, positions, normals, tex_coords) = if normals.is_empty() {
+            let corners: Vec<usize> = triangles.iter().flat_map(|tri| tri.to_array()).map(|id| id as usize).collect();
+            let flat_normals = corners
                 .chunks_exact(3)
-                .map(|tri| UVec3::new(tri[0], tri[1], tri[2]))
+                .flat_map(|tri| [face_normal(positions[tri[0]], positions[tri[1]], positions[tri[2]]); 3])
                 .collect();
-            result.add_section_from_buffers(&triangles, &positions, &normals, &colors, &tex_coords)
-        }
-        result
+            let corner_uvs = match tex_coords.is_empty() {
+                true => Vec::new(),
+                false => corners.iter().map(|&id| tex_coords[id]).collect(),
+            };
+            (
+                (0..corners.len() as u32).step_by(3).map(|id| UVec3::new(id, id + 1, id + 2)).collect(),
+                corners.iter().map(|&id| positions[id]).collect(),
+                flat_normals,
+                corner_uvs,
+            )
+        } else {
+            (triangles, positions, normals, tex_coords)
+        };
+
+        self.add_material_section_from_buffers(&triangles, &positions, &normals, &[], &tex_coords, material);
+        Ok(())
     }
 }
 
 //pub fn load_from_gltf() -> Mesh {}
 
+//unit normal of the triangle's plane, facing the side its corners go counter clockwise around. Zero if it is degenerate
+pub fn face_normal(a: Vec3, b: Vec3, c: Vec3) -> Vec3 {
+    (b - a).cross(c - a).normalize_or_zero()
+}
+
+//lat/long unit sphere with normals, colors and uvs, shared by the tests and benches/raster.rs.
+//enough triangles to cross plenty of tile borders
+#[doc(hidden)]
+pub fn sphere(rings: u32, segments: u32) -> Mesh {
+    let mut vertices = Vec::new();
+    let mut triangles = Vec::new();
+    for ring in 0..=rings {
+        let theta = ring as f32 / rings as f32 * std::f32::consts::PI;
+        for segment in 0..=segments {
+            let phi = segment as f32 / segments as f32 * std::f32::consts::TAU;
+            let normal = glam::vec3(theta.sin() * phi.cos(), theta.cos(), theta.sin() * phi.sin());
+            vertices.push(Vertex::Construct(
+                normal.extend(1.0),
+                normal,
+                glam::vec3(ring as f32 / rings as f32, 0.5, segment as f32 / segments as f32),
+                glam::vec2(segment as f32 / segments as f32, ring as f32 / rings as f32),
+            ));
+        }
+    }
+    for ring in 0..rings {
+        for segment in 0..segments {
+            let a = ring * (segments + 1) + segment;
+            let b = a + segments + 1;
+            triangles.push(UVec3::new(a, a + 1, b));
+            triangles.push(UVec3::new(a + 1, b + 1, b));
+        }
+    }
+    Mesh::from_vertices(&triangles, &vertices)
+}
+
 impl Default for Mesh {
     fn default() -> Self {
         Self::new()
@@ -224,25 +454,33 @@ impl Default for Mesh {
 impl Add for Mesh {
     type Output = Self;
 
-    fn add(self, rhs: Self) -> Self {
-        let mut result = Self::from_vertices(self.triangles(), self.vertices());
-        result.add_section_from_vertices(rhs.triangles(), rhs.vertices());
-        result
+    fn add(mut self, rhs: Self) -> Self {
+        self += rhs;
+        self
     }
 }
 
 impl AddAssign for Mesh {
+    //rhs keeps its sections, moved behind the ones already here
     fn add_assign(&mut self, rhs: Self) {
-        self.add_section_from_vertices(rhs.triangles(), rhs.vertices());
+        let (vertex_offset, triangle_offset) = (self.vertices.len() as u32, self.triangles.len());
+        self.triangles.extend(rhs.triangles.iter().map(|tri| *tri + vertex_offset));
+        self.vertices.extend_from_slice(&rhs.vertices);
+        self.sections.extend(rhs.sections.into_iter().map(|section| Section {
+            triangles: section.triangles.start + triangle_offset..section.triangles.end + triangle_offset,
+            material: section.material,
+        }));
+        self.double_sided = self.double_sided || rhs.double_sided;
+        self.alpha_mode = self.alpha_mode.max(rhs.alpha_mode);
     }
 }
 
 #[derive(Debug, Clone, Copy)]
-pub struct Triangle {
+pub struct Triangle<V = Vertex> {
     //pub vertices: Vec<Vertex>,
-    pub vert0: Vertex,
-    pub vert1: Vertex,
-    pub vert2: Vertex,
+    pub vert0: V,
+    pub vert1: V,
+    pub vert2: V,
 }
 
 pub enum VerticesOrder {
@@ -254,8 +492,8 @@ pub enum VerticesOrder {
     CBA,
 }
 
-impl Triangle {
-    pub fn Construct(v0: Vertex, v1: Vertex, v2: Vertex) -> Self //construct because I cling to the past and have trouble letting go...
+impl<V: Copy> Triangle<V> {
+    pub fn Construct(v0: V, v1: V, v2: V) -> Self //construct because I cling to the past and have trouble letting go...
     {
         Self {
             vert0: v0,
@@ -264,6 +502,19 @@ impl Triangle {
         }
     }
 
+    pub fn reorder(&self, order: VerticesOrder) -> Self {
+        match order {
+            VerticesOrder::ABC => *self,
+            VerticesOrder::ACB => Self::Construct(self.vert0, self.vert2, self.vert1),
+            VerticesOrder::BAC => Self::Construct(self.vert1, self.vert0, self.vert2),
+            VerticesOrder::BCA => Self::Construct(self.vert1, self.vert2, self.vert0),
+            VerticesOrder::CAB => Self::Construct(self.vert2, self.vert0, self.vert1),
+            VerticesOrder::CBA => Self::Construct(self.vert2, self.vert1, self.vert0),
+        }
+    }
+}
+
+impl Triangle {
     pub fn transform(&self, matrix: &Mat4) -> Self {
         let p0 = *matrix * self.vert0.position.xyz().extend(1.0);
         let p1 = *matrix * self.vert1.position.xyz().extend(1.0);
@@ -277,17 +528,6 @@ impl Triangle {
 
         result
     }
-
-    pub fn reorder(&self, order: VerticesOrder) -> Self {
-        match order {
-            VerticesOrder::ABC => *self,
-            VerticesOrder::ACB => Self::Construct(self.vert0, self.vert2, self.vert1),
-            VerticesOrder::BAC => Self::Construct(self.vert1, self.vert0, self.vert2),
-            VerticesOrder::BCA => Self::Construct(self.vert1, self.vert2, self.vert0),
-            VerticesOrder::CAB => Self::Construct(self.vert2, self.vert0, self.vert1),
-            VerticesOrder::CBA => Self::Construct(self.vert2, self.vert1, self.vert0),
-        }
-    }
 }
 
 impl Default for Triangle {
@@ -299,3 +539,62 @@ impl Default for Triangle {
         }
     }
 }
+
+//clipped triangle after perspective division, mapped to the viewport.
+//everything the rasterizer needs per pixel is set up once here so the same triangle can be drawn into several tiles
+#[derive(Debug, Copy, Clone)]
+pub struct ScreenTriangle<V = Vertex> {
+    pub vertices: [V; 3], //attributes divided by clip w, for perspective correct interpolation
+    pub rec: Vec3,        //1 / clip w per vertex
+    pub ndc: [Vec4; 3],
+    pub screen: [Vec2; 3], //snapped to the sub pixel grid
+    pub edges: EdgeSetup,
+    pub area: f32,
+    pub clip_piece: Option<usize>, //which part of a clipped triangle this is, see RenderType::ClipDebug
+}
+
+impl<V: Varying> ScreenTriangle<V> {
+    pub fn new(tri: &Triangle<V>, viewport: &Rect) -> Self {
+        let rec = glam::vec3(
+            1.0 / tri.vert0.position().w,
+            1.0 / tri.vert1.position().w,
+            1.0 / tri.vert2.position().w,
+        );
+
+        //Normalized Device Coordinates
+        //perform perspective division to transform in ndc. xyz components of ndc are now between -1 and 1 (if within frustum)
+        let ndc = [
+            tri.vert0.position() * rec.x,
+            tri.vert1.position() * rec.y,
+            tri.vert2.position() * rec.z,
+        ];
+
+        //remap NDC (-1/1) xy axes to the viewport rectangle
+        let (origin, size) = (viewport.origin(), viewport.size());
+        let screen = ndc.map(|ndc| {
+            let screen = glam::vec2(
+                map_to_range(ndc.x, -1.0, 1.0, origin.x, origin.x + size.x),
+                map_to_range(-ndc.y, -1.0, 1.0, origin.y, origin.y + size.y),
+            );
+            FixedPoint::from_screen(screen).to_screen()
+        });
+
+        Self {
+            vertices: [tri.vert0 * rec.x, tri.vert1 * rec.y, tri.vert2 * rec.z],
+            rec,
+            ndc,
+            screen,
+            edges: EdgeSetup::new(&screen),
+            area: edge_function(screen[0], screen[1], screen[2]),
+            clip_piece: None,
+        }
+    }
+
+    //perspective correct attributes at barycentric coordinates bary
+    #[inline]
+    pub fn interpolate(&self, bary: Vec3) -> V {
+        let [v0, v1, v2] = self.vertices;
+        let correction = 1.0 / (bary.x * self.rec.x + bary.y * self.rec.y + bary.z * self.rec.z);
+        (v0 * bary.x + v1 * bary.y + v2 * bary.z) * correction
+    }
+}
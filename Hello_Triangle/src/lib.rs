@@ -1,36 +1,108 @@
-use glam::{Vec2, Vec3Swizzles, Mat4, Vec4, Vec4Swizzles, Vec3};
-use std::{path::Path, thread};
+use glam::{Vec2, Mat4, Vec3, Vec4};
+use std::path::Path;
 //pub mod files. Important because this exposes these modules from other files to whoever uses lib.rs
 pub mod geometry;
 pub mod texture;
 pub mod utils;
 pub mod transform;
 pub mod camera;
+pub mod clipping;
+pub mod shader;
+pub mod tile;
+pub mod export;
+pub mod render_target;
+pub mod edge;
+pub mod blend;
+pub mod depth;
+pub mod stencil;
+pub mod sampler;
+pub mod material;
+pub mod scene;
+pub mod error;
+pub mod obj;
+pub mod stl;
+pub mod ply;
 pub use {
     geometry::*, 
-    texture::Texture, 
+    texture::{Texture, TextureError}, 
     transform::Transform, 
     camera::Camera,
+    clipping::*,
+    shader::{DefaultShader, Derivatives, FragmentShader, VertexShader, lambert},
+    tile::{TargetSlice, TileBins, TILE_SIZE, raster_tiles},
+    export::{ImageFormat, display_depth, write_color, write_depth},
+    render_target::{Attachment, Rect, RenderTarget, SampleCount},
+    edge::{EdgeSetup, FixedPoint, SUBPIXEL_BITS, sample_offsets},
+    blend::{AlphaMode, BlendState, argb8_to_rgba, rgba_to_argb8},
+    depth::{CompareFunction, DepthState},
+    stencil::{StencilOp, StencilState},
+    sampler::{Filter, Sampler, Wrap},
+    material::{Material, load_gltf_textures},
+    scene::{Node, Scene},
+    error::Error,
+    obj::{Obj, ObjMaterial, load_mtl, load_obj},
+    stl::load_stl,
+    ply::load_ply,
     utils::*};
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum RenderType{
     Std,
     Depth,
     ClipDebug,
-    Wireframe,
+    Wireframe { overlay: bool }, //overlay draws the edges on top of the shaded mesh instead of on black
 }
 
-pub enum ClipResult {
-    None,
-    One(Triangle),
-    Two((Triangle, Triangle)),
+#[derive(Debug, Copy, Clone)]
+pub struct RasterState {
+    pub render_type: RenderType,
+    pub guard_band: f32, //see clipping::DEFAULT_GUARD_BAND
+    pub cull_mode: CullMode, //ignored for double sided meshes
+    pub front_face: FrontFace,
+    pub blend: BlendState,
+    pub depth: DepthState,
+    pub stencil: Option<StencilState>, //None skips the stencil test and leaves the buffer alone
 }
 
+impl Default for RasterState {
+    fn default() -> Self {
+        Self {
+            render_type: RenderType::Std,
+            guard_band: DEFAULT_GUARD_BAND,
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::CounterClockwise,
+            blend: BlendState::Replace,
+            depth: DepthState::default(),
+            stencil: None,
+        }
+    }
+}
+
+const WIREFRAME_COLOR: u32 = 0xFFFFFFFF;
+//edges lie exactly on the surface they outline, give them a bit of slack in the depth test
+const WIREFRAME_DEPTH_BIAS: f32 = 0.0001;
+
+const CLIP_DEBUG_COLORS: [Vec3; 3] = [
+    glam::const_vec3!([1.0, 0.0, 0.0]),
+    glam::const_vec3!([0.0, 1.0, 0.0]),
+    glam::const_vec3!([0.0, 0.0, 1.0]),
+];
+const CLIP_DEBUG_UNCLIPPED: Vec3 = glam::const_vec3!([0.5, 0.5, 0.5]);
+
 #[cfg(test)] //unit tests in Rust
 mod tests {
-    use crate::geometry::Vertex;
+    use crate::geometry::{sphere, Mesh, Section, Vertex};
     use crate::transform::{Transform, TransformInitialParams};
     use crate::utils::*;
+    use crate::geometry::{ScreenTriangle, Triangle, Varying, VerticesOrder};
+    use crate::edge::{EdgeSetup, FixedPoint};
+    use crate::{
+        clip_cull_triangle, cull_triangle_backface, raster_mesh, triangle_screen_bounding_box, raster_mesh_tiled, raster_meshes,
+        AlphaMode, BlendState, Camera, ClipResult, CompareFunction, CullMode, DefaultShader, DepthState, FragmentShader,
+        Filter, FrontFace, RasterState, Rect, RenderTarget, RenderType, Sampler, SampleCount, StencilOp, StencilState,
+        Texture, TextureError, VertexShader, Wrap, load_gltf,
+    };
+    use glam::{Mat4, UVec3};
 
     #[test]
     fn lerping() {
@@ -60,317 +132,1698 @@ mod tests {
         ));
              assert_eq!(transform.translation.x, translation.x);
     }
-}
 
-pub fn Raster_Clipped_Triangle(
-    tri: &Triangle, 
-    buffer: &mut Vec<u32>, 
-    texture: Option<&Texture>, 
-    z_buffer: &mut Vec<f32>, 
-    viewport_size: Vec2, 
-    rtype: &RenderType)
-{
-    let rec0 = 1.0 / tri.vert0.position.w;
-    let rec1 = 1.0 / tri.vert1.position.w;
-    let rec2 = 1.0 / tri.vert2.position.w;
-
-    //Normalized Device Coordinates
-    //perform perspective division to transform in ndc. xyz components of ndc are now between -1 and 1 (if within frustum)
-    //normally the output of the vertex shader 
-    let ndc0 = tri.vert0.position * rec0; //since clip.w is -1 to 1, map clip space coords to -1/1
-    let ndc1 = tri.vert1.position * rec1;
-    let ndc2 = tri.vert2.position * rec2;
-
-    let v0 = tri.vert0 * rec0;
-    let v1 = tri.vert1 * rec1;
-    let v2 = tri.vert2 * rec2;
-
-    //remap NDC (-1/1) xy axes to viewport size (width/height)
-    let sc0 = glam::vec2 (
-        map_to_range(ndc0.x, -1.0, 1.0, 0.0, viewport_size.x),
-        map_to_range(-ndc0.y, -1.0, 1.0, 0.0, viewport_size.y),
-    );
-
-    let sc1 = glam::vec2 (
-        map_to_range(ndc1.x, -1.0, 1.0, 0.0, viewport_size.x),
-        map_to_range(-ndc1.y, -1.0, 1.0, 0.0, viewport_size.y),
-    );
-
-    let sc2 = glam::vec2(
-        map_to_range(ndc2.x, -1.0, 1.0, 0.0, viewport_size.x),
-        map_to_range(-ndc2.y, -1.0, 1.0, 0.0, viewport_size.y),
-    );
-
-    if let Some(bb) = triangle_screen_bounding_box(&[sc0, sc1, sc2], viewport_size) {
-
-        //spawn threads here? every x and y % 32 == 0
-
-        // let handle = thread::spawn(|| {
-        //     //
-        // });
-
-        for y in (bb.top as usize)..=bb.bot as usize {
-            for x in (bb.left as usize)..=bb.right as usize {
-                let coords = glam::vec2(x as f32, y as f32) + 0.5;
-                let pixel_id = coords_to_index(x, y, viewport_size.x as usize);
-                let area = edge_function(sc0, sc1, sc2);
-
-                if let Some(bary) = Barycentric_Coordinates(coords,sc0,sc1,sc2, area) {
-                    let correction = bary.x * rec0 + bary.y * rec1 + bary.z * rec2;
-                    let correction = 1.0 / correction;
-                    let depth = bary.x * ndc0.z + bary.y * ndc1.z + bary.z * ndc2.z;
-                    if depth < z_buffer[pixel_id] {
-                        z_buffer[pixel_id] = depth;
+    #[test]
+    fn tiled_matches_single_threaded() {
+        //odd size so the last row and column of tiles are partial
+        let (width, height) = (150, 110);
+        let mesh = sphere(24, 32);
 
-                        let normal = bary.x * v0.normal + bary.y * v1.normal + bary.z * v2.normal;
-                        let normal = normal * correction;
+        let camera = Camera {
+            aspect_ratio: width as f32 / height as f32,
+            //close enough that part of the sphere gets clipped by the near plane
+            transform: Transform::from_translation(glam::vec3(0.3, 0.2, 1.05)),
+            ..Default::default()
+        };
+        let model = Transform::from_rotation(glam::Quat::from_rotation_y(0.4)).local();
+        let mvp = camera.projection() * camera.view() * model;
+        let shader = DefaultShader::new(&model, &mvp, None);
 
-                        let n_dot_l = normal.dot(Vec3::ONE.normalize());    //normalize vertex normals
+        for render_type in [
+            RenderType::Std,
+            RenderType::Depth,
+            RenderType::ClipDebug,
+            RenderType::Wireframe { overlay: false },
+            RenderType::Wireframe { overlay: true },
+        ] {
+            let state = RasterState {
+                render_type,
+                ..Default::default()
+            };
 
-                        let color = bary.x * v0.color + bary.y * v1.color + bary.z * v2.color;
-                        let mut color = color * correction;
+            //scissor edges don't line up with tile borders either
+            let scissors = [None, Some(Rect::new(13, 7, 101, 77))];
+            for (scissor, samples) in scissors.into_iter().zip([SampleCount::X1, SampleCount::X4]) {
+                let mut target = RenderTarget::with_samples(width, height, samples);
+                target.scissor = scissor;
+                raster_mesh(&mesh, &shader, &mut target, &state);
+                target.resolve();
 
-                        
-                        if let Some(tex) = texture {
-                            let texCoords = bary.x * v0.uv + bary.y * v1.uv + bary.z * v2.uv;
-                            let texCoords = texCoords * correction;
-                            color = tex.argb_at_uvf(texCoords.x, texCoords.y).yzw();
-                        }
-                        
-                        let ambient = glam::vec3(0.2, 0.2, 0.2);
-                        color = color * n_dot_l + ambient;
-                        let mut out_color = to_argb8(
-                            255, 
-                            (color.x * 255.0) as u8,
-                            (color.y * 255.0) as u8,
-                            (color.z * 255.0) as u8,
-                        );
-
-                        if let RenderType::Depth = rtype {
-                            out_color = to_argb8(
-                                255,
-                                (depth * 255.0) as u8,
-                                (depth * 255.0) as u8,
-                                (depth * 255.0) as u8,
-                            );
-                        }
+                let mut tiled_target = RenderTarget::with_samples(width, height, samples);
+                tiled_target.scissor = scissor;
+                raster_mesh_tiled(&mesh, &shader, &mut tiled_target, &state);
+                tiled_target.resolve();
 
-                        buffer[pixel_id] = out_color; //write to buffer
-                    }
+                assert!(target.color.iter().any(|&c| c != 0));
+                assert_eq!(target.color, tiled_target.color);
+                assert_eq!(target.depth, tiled_target.depth);
+            }
+        }
+    }
+
+    #[test]
+    fn fragment_shader_discard() {
+        //keeps the default vertex stage but throws away every pixel
+        struct DiscardShader<'a>(DefaultShader<'a>);
+        impl VertexShader for DiscardShader<'_> {
+            type Varying = Vertex;
 
-                }   
+            fn vertex(&self, vertex: &Vertex) -> Vertex {
+                self.0.vertex(vertex)
+            }
+        }
+        impl FragmentShader for DiscardShader<'_> {
+            fn fragment(&self, _: &Vertex) -> Option<glam::Vec4> {
+                None
             }
         }
+
+        let camera = Camera {
+            transform: Transform::from_translation(glam::vec3(0.0, 0.0, 3.0)),
+            ..Default::default()
+        };
+        let mvp = camera.projection() * camera.view();
+        let shader = DiscardShader(DefaultShader::new(&glam::Mat4::IDENTITY, &mvp, None));
+
+        let mut target = RenderTarget::new(64, 64);
+        let stats = raster_mesh(&sphere(8, 8), &shader, &mut target, &RasterState::default());
+
+        assert!(stats.culled > 0);
+        assert!(target.color.iter().all(|&c| c == 0));
+        assert!(target.depth.iter().all(|&z| z == f32::INFINITY));
     }
-}
 
-pub fn Raster_Triangle(
-    tri: &Triangle,
-    model_mat: &Mat4,
-    mvp: &Mat4, 
-    texture: Option<&Texture>,
-    buffer: &mut Vec<u32>,
-    z_buffer: &mut Vec<f32>,
-    viewport_size: Vec2,
-    rtype: &RenderType,
-){
-    let cof_mat = cofactor(model_mat);
+    #[test]
+    fn custom_varying() {
+        //only carries what the fragment stage needs, should draw the same as a full Vertex
+        #[derive(Clone, Copy)]
+        struct ColorVarying {
+            position: glam::Vec4,
+            color: glam::Vec3,
+        }
+        impl std::ops::Add for ColorVarying {
+            type Output = Self;
+            fn add(self, rhs: Self) -> Self {
+                Self { position: self.position + rhs.position, color: self.color + rhs.color }
+            }
+        }
+        impl std::ops::Sub for ColorVarying {
+            type Output = Self;
+            fn sub(self, rhs: Self) -> Self {
+                Self { position: self.position - rhs.position, color: self.color - rhs.color }
+            }
+        }
+        impl std::ops::Mul<f32> for ColorVarying {
+            type Output = Self;
+            fn mul(self, rhs: f32) -> Self {
+                Self { position: self.position * rhs, color: self.color * rhs }
+            }
+        }
+        impl Varying for ColorVarying {
+            fn position(&self) -> glam::Vec4 {
+                self.position
+            }
+        }
+
+        struct ColorShader(glam::Mat4);
+        impl VertexShader for ColorShader {
+            type Varying = ColorVarying;
+
+            fn vertex(&self, vertex: &Vertex) -> ColorVarying {
+                ColorVarying { position: self.0 * vertex.position.truncate().extend(1.0), color: vertex.color }
+            }
+        }
+        impl FragmentShader<ColorVarying> for ColorShader {
+            fn fragment(&self, attributes: &ColorVarying) -> Option<glam::Vec4> {
+                Some(attributes.color.extend(1.0))
+            }
+        }
 
-    let mut clip_tri = tri.transform(mvp);
-    clip_tri.vert0.normal = (cof_mat * tri.vert0.normal.extend(0.0)).xyz();
-    clip_tri.vert1.normal = (cof_mat * tri.vert1.normal.extend(0.0)).xyz();
-    clip_tri.vert2.normal = (cof_mat * tri.vert2.normal.extend(0.0)).xyz();
+        struct VertexColorShader(glam::Mat4);
+        impl VertexShader for VertexColorShader {
+            type Varying = Vertex;
 
-    match clip_cull_triangle(&clip_tri) {
-        ClipResult::None => {} //lookup lambda in rust
-        ClipResult::One(ctri) => {
-            Raster_Clipped_Triangle(&ctri, buffer, texture, z_buffer, viewport_size, rtype);   
+            fn vertex(&self, vertex: &Vertex) -> Vertex {
+                let mut out = *vertex;
+                out.position = self.0 * vertex.position.truncate().extend(1.0);
+                out
+            }
         }
-        ClipResult::Two(ctri) => {
-            Raster_Clipped_Triangle(&ctri.0, buffer, texture, z_buffer, viewport_size, rtype);
-            Raster_Clipped_Triangle(&ctri.1, buffer, texture, z_buffer, viewport_size, rtype);
+        impl FragmentShader for VertexColorShader {
+            fn fragment(&self, attributes: &Vertex) -> Option<glam::Vec4> {
+                Some(attributes.color.extend(1.0))
+            }
         }
+
+        let camera = Camera {
+            transform: Transform::from_translation(glam::vec3(0.0, 0.0, 3.0)),
+            ..Default::default()
+        };
+        let mvp = camera.projection() * camera.view();
+        let mesh = sphere(8, 8);
+
+        let mut target = RenderTarget::new(64, 64);
+        raster_mesh(&mesh, &VertexColorShader(mvp), &mut target, &RasterState::default());
+
+        let mut custom_target = RenderTarget::new(64, 64);
+        raster_mesh_tiled(&mesh, &ColorShader(mvp), &mut custom_target, &RasterState::default());
+
+        assert!(target.color.iter().any(|&c| c != 0));
+        assert_eq!(target.color, custom_target.color);
+        assert_eq!(target.depth, custom_target.depth);
     }
-}
 
-//View Frustum Culling
-pub fn cull_triangle_view_frustum(tri: &Triangle) -> bool {
-    // cull tests against the 6 planes
-    if tri.vert0.position.x > tri.vert0.position.w
-        && tri.vert1.position.x > tri.vert1.position.w
-        && tri.vert2.position.x > tri.vert2.position.w
-    {
-        return true;
-    }
-    if tri.vert0.position.x < -tri.vert0.position.w
-        && tri.vert1.position.x < -tri.vert1.position.w
-        && tri.vert2.position.x < -tri.vert2.position.w
-    {
-        return true;
-    }
-    if tri.vert0.position.y > tri.vert0.position.w
-        && tri.vert1.position.y > tri.vert1.position.w
-        && tri.vert2.position.y > tri.vert2.position.w
-    {
-        return true;
-    }
-    if tri.vert0.position.y < -tri.vert0.position.w
-        && tri.vert1.position.y < -tri.vert1.position.w
-        && tri.vert2.position.y < -tri.vert2.position.w
-    {
-        return true;
-    }
-    if tri.vert0.position.z > tri.vert0.position.w
-        && tri.vert1.position.z > tri.vert1.position.w
-        && tri.vert2.position.z > tri.vert2.position.w
-    {
-        return true;
-    }
-    if tri.vert0.position.z < 0.0 && tri.vert1.position.z < 0.0 && tri.vert2.position.z < 0.0
-    {
-        return true;
-    }
-
-    false
-}
+    #[test]
+    fn viewport_and_scissor() {
+        let camera = Camera {
+            transform: Transform::from_translation(glam::vec3(0.0, 0.0, 2.5)),
+            ..Default::default()
+        };
+        let mvp = camera.projection() * camera.view();
+        let shader = DefaultShader::new(&glam::Mat4::IDENTITY, &mvp, None);
+        let mesh = sphere(12, 16);
+        let state = RasterState::default();
 
-pub fn cull_triangle_backface(tri: &Triangle) -> bool {
-    let normal = tri.vert1.position.xyz() - tri.vert0.position.xyz().cross(tri.vert2.position.xyz() - tri.vert0.position.xyz());
-    let view_dir = -Vec3::Z;
+        let mut small = RenderTarget::new(48, 48);
+        raster_mesh(&mesh, &shader, &mut small, &state);
 
-    normal.dot(view_dir) >= 0.0
-}
+        //the same picture moved into a corner of a bigger target, the rest stays cleared
+        let mut big = RenderTarget::new(100, 70);
+        big.clear(0xFF00FF00, f32::INFINITY);
+        big.viewport = Rect::new(40, 20, 48, 48);
+        raster_mesh_tiled(&mesh, &shader, &mut big, &state);
+        for y in 0..70 {
+            for x in 0..100 {
+                let color = big.color[coords_to_index(x, y, 100)];
+                let inside = (40..88).contains(&x) && (20..68).contains(&y);
+                let small_id = if inside { coords_to_index(x - 40, y - 20, 48) } else { 0 };
+                if inside && small.depth[small_id] != f32::INFINITY {
+                    assert_eq!(color, small.color[small_id]);
+                } else {
+                    assert_eq!(color, 0xFF00FF00);
+                }
+            }
+        }
 
-fn clip_triangle_two(tri: &Triangle) -> (Triangle, Triangle) {
-    let alpha_a = (-tri.vert0.position.z) / (tri.vert1.position.z - tri.vert0.position.z);
-    let alpha_b = (-tri.vert0.position.z) / (tri.vert2.position.z - tri.vert0.position.z);
+        //scissor cuts pixels away without moving the picture
+        let mut scissored = RenderTarget::new(48, 48);
+        scissored.scissor = Some(Rect::new(10, 0, 20, 30));
+        raster_mesh(&mesh, &shader, &mut scissored, &state);
+        for y in 0..48 {
+            for x in 0..48 {
+                let id = coords_to_index(x, y, 48);
+                let expected = if (10..30).contains(&x) && y < 30 { small.color[id] } else { 0 };
+                assert_eq!(scissored.color[id], expected);
+            }
+        }
 
-    //interpolate vertices 
-    let mut v0a = Lerp(tri.vert0, tri.vert1, alpha_a);
-    let mut v0b = Lerp(tri.vert0, tri.vert2, alpha_b);
+        //clear leaves extra attachments at their own clear value
+        let normals = scissored.add_attachment(glam::Vec4::Z);
+        scissored.attachments[normals].data[0] = glam::Vec4::ONE;
+        scissored.clear(0, 1.0);
+        assert!(scissored.attachments[normals].data.iter().all(|&n| n == glam::Vec4::Z));
+        assert!(scissored.color.iter().all(|&c| c == 0));
+        assert!(scissored.depth.iter().all(|&z| z == 1.0));
 
-    let green = Vec3::new(0.0, 1.0, 0.0);
-    let blue = Vec3::new(0.0, 0.0, 1.0);
-    
-    let mut result_a = *tri;
-    let mut result_b = *tri;
+        //resize keeps the scissor inside the target and a full viewport full
+        scissored.resize(20, 60);
+        assert_eq!(scissored.scissor, Some(Rect::new(10, 0, 10, 30)));
+        assert_eq!(scissored.viewport, Rect::new(0, 0, 20, 60));
+        assert_eq!(scissored.attachments[normals].data.len(), 20 * 60);
+        big.resize(120, 80);
+        assert_eq!(big.viewport, Rect::new(40, 20, 48, 48));
+        assert_eq!(big.scissor, None);
+    }
 
-    result_a.vert0 = v0a;
+    #[test]
+    fn clipped_fan_stays_in_guard_band() {
+        //way past every side plane and through the near plane
+        let vertex = |x: f32, y: f32, z: f32| Vertex {
+            position: glam::vec4(x, y, z, 1.0),
+            ..Default::default()
+        };
+        let a = vertex(-500.0, -400.0, 0.5);
+        let b = vertex(600.0, -300.0, 0.5);
+        let c = vertex(0.0, 700.0, -0.5);
+        let guard_band = 1.5;
 
-    result_b.vert0 = v0a;
-    result_b.vert1 = v0b;
+        let ClipResult::Fan(ctris) = clip_cull_triangle(&Triangle::Construct(a, b, c), guard_band) else {
+            panic!("triangle should have been clipped");
+        };
+        assert!(ctris.len() > 1);
+        for ctri in &ctris {
+            for v in [ctri.vert0, ctri.vert1, ctri.vert2] {
+                let p = v.position;
+                assert!(p.x.abs() <= p.w * guard_band + 0.001);
+                assert!(p.y.abs() <= p.w * guard_band + 0.001);
+                assert!(p.z >= -0.001 && p.z <= p.w + 0.001);
+            }
+        }
+    }
 
-    result_a.vert0.color = green;
-    result_a.vert1.color = green;
-    result_a.vert2.color = green;
-    result_b.vert0.color = blue;
-    result_b.vert1.color = blue;
-    result_b.vert2.color = blue;
+    #[test]
+    fn msaa_smooths_edges() {
+        //flat white triangle, any grey pixel comes from partial coverage
+        struct White;
+        impl VertexShader for White {
+            type Varying = Vertex;
 
-    (result_a, result_b)    //not sure what type this actually is. Vector? Array? Tuple?
+            fn vertex(&self, vertex: &Vertex) -> Vertex {
+                *vertex
+            }
+        }
+        impl FragmentShader for White {
+            fn fragment(&self, _: &Vertex) -> Option<glam::Vec4> {
+                Some(glam::Vec4::ONE)
+            }
+        }
+        let vertex = |x: f32, y: f32| Vertex {
+            position: glam::vec4(x, y, 0.5, 1.0),
+            ..Default::default()
+        };
+        let mesh = Mesh::from_vertices(&[UVec3::new(0, 1, 2)], &[vertex(-0.8, -0.7), vertex(0.9, -0.2), vertex(-0.3, 0.8)]);
+
+        let render = |samples| {
+            let mut target = RenderTarget::with_samples(40, 40, samples);
+            raster_mesh_tiled(&mesh, &White, &mut target, &RasterState::default());
+            target.resolve();
+            target
+        };
+        let single = render(SampleCount::X1);
+        assert!(single.color.iter().all(|&c| c == 0 || c == 0xFFFFFFFF));
+
+        for samples in [SampleCount::X2, SampleCount::X4, SampleCount::X8] {
+            let msaa = render(samples);
+            let partial = msaa.color.iter().filter(|&&c| c != 0 && c != 0xFFFFFFFF).count();
+            assert!(partial > 0, "{:?}", samples);
+
+            //pixels well inside or outside the triangle don't change
+            for (id, &c) in single.color.iter().enumerate() {
+                let (x, y) = index_to_coords(id, 40);
+                let neighbours_agree = (0..9).all(|n| {
+                    let (nx, ny) = ((x + n % 3).saturating_sub(1).min(39), (y + n / 3).saturating_sub(1).min(39));
+                    single.color[coords_to_index(nx, ny, 40)] == c
+                });
+                if neighbours_agree {
+                    assert_eq!(msaa.color[id], c, "{:?} at {}, {}", samples, x, y);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn blend_states() {
+        let src = glam::vec4(1.0, 0.0, 0.0, 0.25);
+        let dst = glam::vec4(0.0, 0.0, 1.0, 1.0);
+        let cases = [
+            (BlendState::Replace, src),
+            (BlendState::Alpha, glam::vec4(0.25, 0.0, 0.75, 1.0)),
+            (BlendState::Additive, glam::vec4(0.25, 0.0, 1.0, 1.0)),
+            (BlendState::Premultiplied, glam::vec4(1.0, 0.0, 0.75, 1.0)),
+            (BlendState::Multiply, glam::vec4(0.0, 0.0, 0.0, 1.0)),
+        ];
+        for (blend, expected) in cases {
+            assert!(blend.blend(src, dst).abs_diff_eq(expected, 1e-6), "{:?}", blend);
+        }
+
+        assert_eq!(AlphaMode::Opaque.apply(0.1), Some(1.0));
+        assert_eq!(AlphaMode::Mask { cutoff: 0.5 }.apply(0.4), None);
+        assert_eq!(AlphaMode::Mask { cutoff: 0.5 }.apply(0.6), Some(1.0));
+        assert_eq!(AlphaMode::Blend.apply(0.3), Some(0.3));
+    }
+
+    #[test]
+    fn transparent_pass() {
+        //every mesh is a screen filling quad at its own depth in one flat color
+        struct FlatShader {
+            mvp: glam::Mat4,
+            color: glam::Vec4,
+        }
+        impl VertexShader for FlatShader {
+            type Varying = Vertex;
+
+            fn vertex(&self, vertex: &Vertex) -> Vertex {
+                Vertex {
+                    position: self.mvp * vertex.position,
+                    ..*vertex
+                }
+            }
+        }
+        impl FragmentShader for FlatShader {
+            fn fragment(&self, _: &Vertex) -> Option<glam::Vec4> {
+                Some(self.color)
+            }
+        }
+        let quad = |z: f32, alpha_mode: AlphaMode| {
+            let vertex = |x: f32, y: f32| Vertex {
+                position: glam::vec4(x, y, z, 1.0),
+                ..Default::default()
+            };
+            let vertices = [vertex(-5.0, -5.0), vertex(5.0, -5.0), vertex(5.0, 5.0), vertex(-5.0, 5.0)];
+            let mut mesh = Mesh::from_vertices(&[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)], &vertices);
+            mesh.set_alpha_mode(alpha_mode);
+            mesh
+        };
+        let mvp = glam::Mat4::perspective_rh(1.0, 1.0, 0.1, 10.0);
+        let shader = |r: f32, g: f32, b: f32, a: f32| FlatShader {
+            mvp,
+            color: glam::vec4(r, g, b, a),
+        };
+
+        let (far, middle, near) = (quad(-3.0, AlphaMode::Opaque), quad(-2.0, AlphaMode::Blend), quad(-1.0, AlphaMode::Blend));
+        let (blue, red, green) = (shader(0.0, 0.0, 1.0, 1.0), shader(1.0, 0.0, 0.0, 0.5), shader(0.0, 1.0, 0.0, 0.5));
+
+        //draw order in the list must not matter
+        let orders = [
+            [(&near, &green), (&far, &blue), (&middle, &red)],
+            [(&middle, &red), (&near, &green), (&far, &blue)],
+        ];
+        let mut opaque_only = RenderTarget::new(8, 8);
+        raster_meshes(&[(&far, &blue)], &mut opaque_only, &RasterState::default());
+        for order in orders {
+            let mut target = RenderTarget::new(8, 8);
+            raster_meshes(&order, &mut target, &RasterState::default());
+
+            //red over blue, then green over that
+            let (_, r, g, b) = from_argb8(target.color[coords_to_index(4, 4, 8)]);
+            assert_eq!((r, g, b), (63, 127, 63));
+            //transparent meshes don't write depth
+            assert_eq!(target.depth, opaque_only.depth);
+        }
+
+        //a blend state other than Replace is kept for the transparent pass
+        let mut target = RenderTarget::new(8, 8);
+        let additive = RasterState {
+            blend: BlendState::Additive,
+            ..Default::default()
+        };
+        raster_meshes(&[(&far, &blue)], &mut target, &RasterState::default());
+        raster_meshes(&[(&middle, &red)], &mut target, &additive);
+        let (_, r, g, b) = from_argb8(target.color[coords_to_index(4, 4, 8)]);
+        assert_eq!((r, g, b), (127, 0, 255));
+
+        //masked pixels are either discarded or fully opaque
+        let mut target = RenderTarget::new(8, 8);
+        let mut masked = quad(-1.0, AlphaMode::Mask { cutoff: 0.6 });
+        raster_meshes(&[(&far, &blue), (&masked, &green)], &mut target, &RasterState::default());
+        assert_eq!(target.color, opaque_only.color);
+        masked.set_alpha_mode(AlphaMode::Mask { cutoff: 0.4 });
+        raster_meshes(&[(&masked, &green)], &mut target, &RasterState::default());
+        assert!(target.color.iter().all(|&c| c == to_argb8(255, 0, 255, 0)));
+    }
+
+    #[test]
+    fn depth_compare_functions() {
+        use CompareFunction::*;
+        //results for value 1 against stored 1, 2 and 0
+        let cases = [
+            (Never, [false, false, false]),
+            (Less, [false, true, false]),
+            (LessEqual, [true, true, false]),
+            (Equal, [true, false, false]),
+            (GreaterEqual, [true, false, true]),
+            (Greater, [false, false, true]),
+            (NotEqual, [false, true, true]),
+            (Always, [true, true, true]),
+        ];
+        for (compare, expected) in cases {
+            assert_eq!([1.0, 2.0, 0.0].map(|stored| compare.test(1.0, stored)), expected, "{:?}", compare);
+        }
+        assert!(Less.test_biased(1.05, 1.0, 0.1));
+        assert!(Greater.test_biased(0.95, 1.0, 0.1));
+    }
+
+    #[test]
+    fn reverse_z_and_depth_prepass() {
+        let (width, height) = (64, 48);
+        let mesh = sphere(12, 16) + sphere(6, 8);
+        let render = |reverse_z: bool, depth: DepthState| {
+            let camera = Camera {
+                aspect_ratio: width as f32 / height as f32,
+                //close enough for the near plane to cut the sphere
+                transform: Transform::from_translation(glam::vec3(0.3, 0.2, 1.05)),
+                reverse_z,
+                ..Default::default()
+            };
+            let shader = DefaultShader::new(&glam::Mat4::IDENTITY, &(camera.projection() * camera.view()), None);
+            let state = RasterState {
+                depth,
+                ..Default::default()
+            };
+            let mut target = RenderTarget::new(width, height).with_reverse_z(reverse_z);
+            assert!(target.depth.iter().all(|&z| z == target.far_depth()));
+            raster_mesh(&mesh, &shader, &mut target, &state);
+            (target, shader, state)
+        };
+
+        let (forward, forward_shader, forward_state) = render(false, DepthState::default());
+        let (reversed, reversed_shader, reversed_state) = render(true, DepthState::reverse_z());
+        assert!(forward.color.iter().any(|&c| c != 0));
+        assert_eq!(forward.color, reversed.color);
+        for (f, r) in forward.depth.iter().zip(&reversed.depth) {
+            assert_eq!(f.is_finite(), r.is_finite());
+        }
+
+        //the depth view and the depth export look the same either way, near dark and the background white
+        let depth_view = |reverse_z: bool, shader: &DefaultShader, state: RasterState| {
+            let mut target = RenderTarget::new(width, height).with_reverse_z(reverse_z);
+            target.clear_color(0xFFFFFFFF);
+            let state = RasterState {
+                render_type: RenderType::Depth,
+                ..state
+            };
+            raster_mesh_tiled(&mesh, shader, &mut target, &state);
+            target
+        };
+        let forward_view = depth_view(false, &forward_shader, forward_state);
+        let reversed_view = depth_view(true, &reversed_shader, reversed_state);
+        assert!(forward_view.color.iter().any(|&c| from_argb8(c).1 < 200));
+        for (f, r) in forward_view.color.iter().zip(&reversed_view.color) {
+            assert!((from_argb8(*f).1 as i32 - from_argb8(*r).1 as i32).abs() <= 1, "{:x} {:x}", f, r);
+        }
+        for (f, r) in forward.depth.iter().zip(&reversed.depth) {
+            let (f, r) = (crate::export::depth_to_u16(*f, false), crate::export::depth_to_u16(*r, true));
+            assert!((f as i32 - r as i32).abs() <= 64, "{} {}", f, r);
+        }
+        assert_eq!(crate::export::depth_to_u16(reversed.far_depth(), true), u16::MAX);
+
+        //second pass only shades what the pre-pass left in depth, and leaves depth alone
+        let (mut prepass, shader, state) = render(false, DepthState::default());
+        let depth = prepass.depth.clone();
+        prepass.clear_color(0);
+        let equal = RasterState {
+            depth: DepthState {
+                compare: CompareFunction::Equal,
+                write: false,
+            },
+            ..state
+        };
+        raster_mesh(&mesh, &shader, &mut prepass, &equal);
+        assert_eq!(prepass.color, forward.color);
+        assert_eq!(prepass.depth, depth);
+
+        let never = RasterState {
+            depth: DepthState {
+                compare: CompareFunction::Never,
+                write: true,
+            },
+            ..state
+        };
+        let mut target = RenderTarget::new(width, height);
+        raster_mesh(&mesh, &shader, &mut target, &never);
+        assert!(target.color.iter().all(|&c| c == 0));
+    }
+
+    #[test]
+    fn stencil_ops() {
+        let state = StencilState {
+            reference: 0b1010,
+            read_mask: 0b0011,
+            write_mask: 0b0110,
+            ..Default::default()
+        };
+        assert_eq!(state.apply(StencilOp::Keep, 0b1001), 0b1001);
+        assert_eq!(state.apply(StencilOp::Zero, 0b1111), 0b1001);
+        assert_eq!(state.apply(StencilOp::Replace, 0b0000), 0b0010);
+        assert_eq!(state.apply(StencilOp::Invert, 0b0100), 0b0010);
+        assert_eq!(StencilState::default().apply(StencilOp::Increment, 255), 255);
+        assert_eq!(StencilState::default().apply(StencilOp::Decrement, 0), 0);
+        assert_eq!(StencilState::default().apply(StencilOp::Increment, 7), 8);
+
+        //only the low two bits get compared
+        let equal = StencilState {
+            compare: CompareFunction::Equal,
+            ..state
+        };
+        assert!(equal.test(0b1110));
+        assert!(!equal.test(0b1011));
+    }
+
+    #[test]
+    fn stencil_masks_drawing() {
+        //flat color quads straight in ndc
+        struct Flat(glam::Vec4);
+        impl VertexShader for Flat {
+            type Varying = Vertex;
+
+            fn vertex(&self, vertex: &Vertex) -> Vertex {
+                *vertex
+            }
+        }
+        impl FragmentShader for Flat {
+            fn fragment(&self, _: &Vertex) -> Option<glam::Vec4> {
+                Some(self.0)
+            }
+        }
+        let quad = |size: f32, z: f32| {
+            let vertex = |x: f32, y: f32| Vertex {
+                position: glam::vec4(x * size, y * size, z, 1.0),
+                ..Default::default()
+            };
+            let vertices = [vertex(-1.0, -1.0), vertex(1.0, -1.0), vertex(1.0, 1.0), vertex(-1.0, 1.0)];
+            Mesh::from_vertices(&[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)], &vertices)
+        };
+        let (red, green) = (Flat(glam::vec4(1.0, 0.0, 0.0, 1.0)), Flat(glam::vec4(0.0, 1.0, 0.0, 1.0)));
+        let green_argb = to_argb8(255, 0, 255, 0);
+
+        for samples in [SampleCount::X1, SampleCount::X4] {
+            //a portal: the small quad marks the stencil, the big one only shows through it
+            let mut target = RenderTarget::with_samples(16, 16, samples);
+            let mark = RasterState {
+                stencil: Some(StencilState::write(1)),
+                ..Default::default()
+            };
+            raster_mesh_tiled(&quad(0.5, 0.5), &red, &mut target, &mark);
+            let through = RasterState {
+                depth: DepthState {
+                    compare: CompareFunction::Always,
+                    write: false,
+                },
+                stencil: Some(StencilState::equal(1)),
+                ..Default::default()
+            };
+            raster_mesh_tiled(&quad(1.0, 0.9), &green, &mut target, &through);
+            target.resolve();
+
+            for (id, &color) in target.color.iter().enumerate() {
+                let (x, y) = index_to_coords(id, 16);
+                let expected = match (x, y) {
+                    (5..=10, 5..=10) => green_argb,
+                    (4 | 11, _) | (_, 4 | 11) => continue, //edges are partially covered with msaa
+                    _ => 0,
+                };
+                assert_eq!(color, expected, "{:?} at {}, {}", samples, x, y);
+            }
+        }
+
+        //count how often the big quad is hidden behind the small one
+        let mut target = RenderTarget::new(16, 16);
+        raster_mesh(&quad(0.5, 0.5), &red, &mut target, &RasterState::default());
+        let count = RasterState {
+            depth: DepthState {
+                write: false,
+                ..Default::default()
+            },
+            stencil: Some(StencilState {
+                depth_fail: StencilOp::Increment,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        raster_mesh(&quad(1.0, 0.9), &green, &mut target, &count);
+        raster_mesh(&quad(1.0, 0.9), &green, &mut target, &count);
+        for (id, &color) in target.color.iter().enumerate() {
+            let expected = if color == green_argb { 0 } else { 2 };
+            assert_eq!(target.stencil[id], expected);
+        }
+    }
+
+    //black and white texels in a checkerboard
+    fn checker(width: usize, height: usize) -> Texture {
+        let data = (0..width * height)
+            .map(|id| {
+                let (x, y) = index_to_coords(id, width);
+                if (x + y) % 2 == 0 { 0xFF000000 } else { 0xFFFFFFFF }
+            })
+            .collect();
+        Texture::new(width, height, data, 3)
+    }
+
+    #[test]
+    fn mip_chain_and_filtering() {
+        let mut texture = checker(8, 4);
+        assert_eq!(texture.mip_levels(), 4);
+        assert_eq!((0..4).map(|level| (texture.mip(level).0, texture.mip(level).1)).collect::<Vec<_>>(), [(8, 4), (4, 2), (2, 1), (1, 1)]);
+        assert!((1..4).all(|level| texture.mip(level).2.iter().all(|&texel| texel == to_argb8(255, 128, 128, 128))));
+
+        //texel centers give the texel back, halfway between two gives the average
+        let texel = |x: f32, y: f32| glam::vec2((x + 0.5) / 8.0, (y + 0.5) / 4.0);
+        assert_eq!(texture.bilinear(0, texel(1.0, 0.0)), glam::Vec4::ONE);
+        assert_eq!(texture.bilinear(0, texel(0.0, 0.0)), glam::vec4(1.0, 0.0, 0.0, 0.0));
+        assert!(texture.bilinear(0, texel(0.5, 0.0)).abs_diff_eq(glam::vec4(1.0, 0.5, 0.5, 0.5), 1e-6));
+        //wraps around the right edge
+        assert!(texture.bilinear(0, texel(7.5, 0.0)).abs_diff_eq(glam::vec4(1.0, 0.5, 0.5, 0.5), 1e-6));
+
+        let (uv, near, far) = (texel(1.0, 0.0), glam::vec2(0.1 / 8.0, 0.0), glam::vec2(1.0, 0.0));
+        assert_eq!(texture.sample(uv, far, far), glam::Vec4::ONE); //nearest ignores the footprint
+        texture.sampler.filter = Filter::Linear;
+        assert_eq!(texture.sample(uv, far, far), glam::Vec4::ONE); //and so does linear
+        for filter in [Filter::Bilinear, Filter::Trilinear] {
+            texture.sampler.filter = filter;
+            assert_eq!(texture.sample(uv, near, near), glam::Vec4::ONE);
+            assert!(texture.sample(uv, far, far).abs_diff_eq(glam::vec4(1.0, 0.5, 0.5, 0.5), 0.01));
+        }
+        //halfway between level 0 and 1
+        let between = texture.sample(uv, glam::vec2(2.0f32.sqrt() / 8.0, 0.0), glam::Vec2::ZERO);
+        assert!(between.abs_diff_eq(glam::vec4(1.0, 0.75, 0.75, 0.75), 0.01), "{:?}", between);
+    }
+
+    #[test]
+    fn sampler_wrap_modes() {
+        //texel coordinates -5..9 on a 4 texel axis
+        let wrapped = |wrap: Wrap| (-5..9).map(|coord| wrap.texel(coord, 4)).collect::<Vec<_>>();
+        assert_eq!(wrapped(Wrap::Repeat), [3, 0, 1, 2, 3, 0, 1, 2, 3, 0, 1, 2, 3, 0]);
+        assert_eq!(wrapped(Wrap::MirroredRepeat), [3, 3, 2, 1, 0, 0, 1, 2, 3, 3, 2, 1, 0, 0]);
+        assert_eq!(wrapped(Wrap::ClampToEdge), [0, 0, 0, 0, 0, 0, 1, 2, 3, 3, 3, 3, 3, 3]);
+
+        //texel x in red, per axis modes
+        let data = (0..4 * 2).map(|id| to_argb8(255, (id % 4) as u8, 0, 0)).collect();
+        let mut texture = Texture::new(4, 2, data, 3);
+        let red_at = |texture: &Texture, u: f32| from_argb8(texture.argb_at_uv(u, 0.25)).1;
+        assert_eq!([-0.1, 1.1, 2.6].map(|u| red_at(&texture, u)), [3, 0, 2]);
+        texture.sampler.wrap_u = Wrap::MirroredRepeat;
+        assert_eq!([-0.1, 1.1, -1.1].map(|u| red_at(&texture, u)), [0, 3, 3]);
+        texture.sampler.wrap_u = Wrap::ClampToEdge;
+        assert_eq!([-0.1, 1.1, -7.0].map(|u| red_at(&texture, u)), [0, 3, 0]);
+        //v still repeats
+        assert_eq!(texture.uv_to_index(0.1, -0.25), texture.uv_to_index(0.1, 0.75));
+
+        //bilinear at the left border blends with the texel the wrap mode picks
+        texture.sampler.wrap_u = Wrap::Repeat;
+        assert!((texture.bilinear(0, glam::vec2(0.0, 0.25)).y * 255.0 - 1.5).abs() < 1e-4);
+        texture.sampler.wrap_u = Wrap::ClampToEdge;
+        assert_eq!(texture.bilinear(0, glam::vec2(0.0, 0.25)).y, 0.0);
+    }
+
+    #[test]
+    fn sampler_from_gltf() {
+        let json = br#"{
+            "asset": { "version": "2.0" },
+            "samplers": [
+                { "wrapS": 33648, "wrapT": 33071, "minFilter": 9987, "magFilter": 9729 },
+                { "minFilter": 9729 },
+                { "minFilter": 9985 },
+                { "magFilter": 9728 },
+                {}
+            ]
+        }"#;
+        let gltf = gltf::Gltf::from_slice(json).unwrap();
+        let samplers: Vec<Sampler> = gltf.samplers().map(|sampler| Sampler::from_gltf(&sampler)).collect();
+        let sampler = |filter, wrap_u, wrap_v| Sampler { filter, wrap_u, wrap_v };
+        assert_eq!(
+            samplers,
+            [
+                sampler(Filter::Trilinear, Wrap::MirroredRepeat, Wrap::ClampToEdge),
+                sampler(Filter::Linear, Wrap::Repeat, Wrap::Repeat),
+                sampler(Filter::Bilinear, Wrap::Repeat, Wrap::Repeat),
+                sampler(Filter::Nearest, Wrap::Repeat, Wrap::Repeat),
+                sampler(Filter::Trilinear, Wrap::Repeat, Wrap::Repeat),
+            ]
+        );
+    }
+
+    //2x1 png with the given color type
+    fn encode_png(color: png::ColorType, pixels: &[u8]) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 1);
+        encoder.set_color(color);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(pixels).unwrap();
+        writer.finish().unwrap();
+        bytes
+    }
+
+    #[test]
+    fn texture_channels() {
+        let cases = [
+            (png::ColorType::Grayscale, vec![10, 200], 1, [to_argb8(255, 10, 10, 10), to_argb8(255, 200, 200, 200)]),
+            (png::ColorType::GrayscaleAlpha, vec![10, 0, 200, 128], 2, [to_argb8(0, 10, 10, 10), to_argb8(128, 200, 200, 200)]),
+            (png::ColorType::Rgb, vec![1, 2, 3, 4, 5, 6], 3, [to_argb8(255, 1, 2, 3), to_argb8(255, 4, 5, 6)]),
+            (png::ColorType::Rgba, vec![1, 2, 3, 0, 4, 5, 6, 7], 4, [to_argb8(0, 1, 2, 3), to_argb8(7, 4, 5, 6)]),
+        ];
+        for (color, pixels, depth, expected) in cases {
+            let texture = Texture::from_memory(&encode_png(color, &pixels)).unwrap();
+            assert_eq!((texture.width, texture.height, texture.depth), (2, 1, depth));
+            assert_eq!(texture.data, expected);
+        }
+
+        //flat radiance rgbe: 0.5 gray and a 2.0 red that saturates
+        let mut hdr = b"#?RADIANCE\nFORMAT=32-bit_rle_rgbe\n\n-Y 1 +X 2\n".to_vec();
+        hdr.extend([128, 128, 128, 128, 128, 0, 0, 130]);
+        let texture = Texture::from_memory(&hdr).unwrap();
+        assert_eq!(texture.depth, 3);
+        assert_eq!(texture.data, [to_argb8(255, 127, 127, 127), to_argb8(255, 255, 0, 0)]);
+
+        assert!(matches!(Texture::from_memory(b"not an image"), Err(TextureError::Decode(_))));
+        assert!(matches!(Texture::load(std::path::Path::new("missing.png")), Err(TextureError::Io(_))));
+    }
+
+    #[test]
+    fn gltf_material() {
+        let scene = load_gltf(std::path::Path::new("tests/fixtures/textured_quad.gltf")).unwrap();
+        assert_eq!(scene.meshes.len(), 1);
+        assert_eq!(scene.meshes[0].len(), 1);
+        let (mesh, material) = &scene.meshes[0][0];
+
+        assert_eq!(material.base_color_factor, glam::vec4(1.0, 0.5, 0.5, 1.0));
+        assert_eq!(material.tex_coord, 1);
+        assert_eq!(material.alpha_mode, AlphaMode::Mask { cutoff: 0.25 });
+        assert!(material.double_sided && mesh.double_sided());
+        assert_eq!(mesh.alpha_mode(), material.alpha_mode);
+
+        let texture = material.base_color_texture.as_deref().unwrap();
+        assert_eq!((texture.width, texture.height, texture.depth), (2, 2, 3));
+        assert_eq!(
+            texture.data,
+            [to_argb8(255, 255, 0, 0), to_argb8(255, 0, 255, 0), to_argb8(255, 0, 0, 255), to_argb8(255, 255, 255, 255)]
+        );
+        let expected = Sampler { filter: Filter::Linear, wrap_u: Wrap::ClampToEdge, wrap_v: Wrap::MirroredRepeat };
+        assert_eq!(texture.sampler, expected);
+
+        //uvs are TEXCOORD_1, TEXCOORD_0 is all zeros
+        let uvs: Vec<_> = mesh.vertices().iter().map(|v| v.uv).collect();
+        assert_eq!(uvs, [glam::vec2(0.0, 1.0), glam::vec2(1.0, 1.0), glam::vec2(1.0, 0.0), glam::vec2(0.0, 0.0)]);
+        assert_eq!(mesh.triangles(), &[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)]);
+
+        //the factor tints the texture, uv 1,0 is the clamped green corner
+        let shader = DefaultShader::from_material(&Mat4::IDENTITY, &Mat4::IDENTITY, material);
+        let mut vertex = mesh.vertices()[2];
+        vertex.normal = glam::Vec3::ONE.normalize();
+        let color = shader.fragment(&vertex).unwrap();
+        assert!((color - glam::vec4(0.2, 0.7, 0.2, 1.0)).abs().max_element() < 1e-5);
+    }
+
+    #[test]
+    fn gltf_scene_graph() {
+        let scene = load_gltf(std::path::Path::new("tests/fixtures/nodes.gltf")).unwrap();
+        assert_eq!(scene.nodes.len(), 4);
+        assert_eq!(scene.roots, [0, 2]); //the default scene, not the first
+        assert_eq!(scene.nodes[0].children, [1]);
+        assert_eq!(scene.nodes[0].name.as_deref(), Some("parent"));
+        assert_eq!(scene.nodes[0].mesh, None);
+
+        let child = &scene.nodes[1];
+        assert_eq!(child.transform.scale, glam::vec3(1.0, 2.0, 1.0));
+        let close = |a: Mat4, b: Mat4| a.abs_diff_eq(b, 1e-5);
+        let local = Mat4::from_rotation_z(std::f32::consts::FRAC_PI_2) * Mat4::from_scale(glam::vec3(1.0, 2.0, 1.0));
+        assert!(close(child.world, Mat4::from_translation(glam::vec3(2.0, 0.0, 0.0)) * local));
+        //matrices get decomposed
+        let matrix = &scene.nodes[2].transform;
+        assert_eq!((matrix.translation, matrix.scale), (glam::vec3(0.0, 0.0, -1.0), glam::Vec3::splat(2.0)));
+
+        //the node of the other scene isn't drawn
+        let draws = scene.draws();
+        assert_eq!(draws.len(), 2);
+        assert!(close(draws[0].2, child.world) && close(draws[1].2, scene.nodes[2].world));
+
+        //moving the parent moves the child
+        let mut scene = scene;
+        scene.nodes[0].transform.translation = glam::Vec3::ZERO;
+        scene.update_world();
+        assert!(close(scene.nodes[1].world, local));
+
+        //both nodes end up in the image, left and right of the center
+        let camera = Camera {
+            transform: Transform::from_translation(glam::vec3(0.0, 0.0, 8.0)),
+            ..Default::default()
+        };
+        scene.nodes[2].transform.translation = glam::vec3(-2.0, 0.0, 0.0);
+        scene.update_world();
+        let mut target = RenderTarget::new(32, 32);
+        crate::raster_scene(&scene, &Mat4::IDENTITY, &camera, &mut target, &RasterState::default());
+        let drawn = |x: usize| (0..32).any(|y| target.color[y * 32 + x] != 0);
+        assert!(drawn(4) && drawn(16) && !drawn(31));
+
+        //loops and shared children are refused instead of walked forever
+        let hierarchy = |nodes: &str| {
+            let json = format!(r#"{{ "asset": {{ "version": "2.0" }}, "scenes": [{{ "nodes": [0] }}], "nodes": {} }}"#, nodes);
+            let gltf = gltf::Gltf::from_slice(json.as_bytes()).unwrap();
+            crate::Scene::from_gltf(&gltf.document, &[], &[])
+        };
+        assert!(hierarchy(r#"[{ "children": [1] }, {}]"#).is_ok());
+        let loops = [
+            r#"[{ "children": [1] }, { "children": [2] }, { "children": [1] }]"#,
+            r#"[{ "children": [1, 2] }, { "children": [2] }, {}]"#,
+            r#"[{}, { "children": [1] }]"#,
+            r#"[{}, { "children": [2] }, { "children": [1] }]"#,
+        ];
+        for nodes in loops {
+            assert!(matches!(hierarchy(nodes), Err(crate::Error::Invalid(_))), "{}", nodes);
+        }
+    }
+
+    #[test]
+    fn gltf_multi_primitive() {
+        let path = std::path::Path::new("tests/fixtures/multi_primitive.gltf");
+        let (document, buffers, _) = gltf::import(path).unwrap();
+        let mesh = Mesh::load_from_gltf(&document.meshes().next().unwrap(), &buffers).unwrap();
+
+        //a quad, an indexed triangle and an unindexed one, each pointing at its own vertices
+        assert_eq!(mesh.vertices().len(), 10);
+        assert_eq!(
+            mesh.triangles(),
+            &[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3), UVec3::new(4, 5, 6), UVec3::new(7, 8, 9)]
+        );
+        assert_eq!(mesh.vertices()[4].position, glam::vec4(2.0, 0.0, 0.0, 1.0));
+        assert_eq!(mesh.vertices()[9].position, glam::vec4(0.0, 3.0, 0.0, 1.0));
+        let sections = [
+            Section { triangles: 0..2, material: Some(1) },
+            Section { triangles: 2..3, material: Some(0) },
+            Section { triangles: 3..4, material: None },
+        ];
+        assert_eq!(mesh.sections(), &sections);
+        assert!(mesh.double_sided());
+        assert_eq!(mesh.alpha_mode(), AlphaMode::Blend);
+
+        //adding meshes moves the sections of the second one behind the first
+        let sum = mesh.clone() + mesh;
+        assert_eq!(sum.triangles()[4], UVec3::new(10, 11, 12));
+        assert_eq!(sum.sections()[5], Section { triangles: 7..8, material: None });
+
+        //the scene keeps a mesh per primitive with the material it points at
+        let scene = load_gltf(path).unwrap();
+        let primitives = &scene.meshes[0];
+        assert_eq!(primitives.len(), 3);
+        assert_eq!(primitives[0].1.alpha_mode, AlphaMode::Blend);
+        assert_eq!(primitives[1].1.base_color_factor, glam::vec4(1.0, 0.0, 0.0, 1.0));
+        assert_eq!(primitives[2].1.base_color_factor, glam::Vec4::ONE);
+        assert_eq!(primitives[1].0.triangles(), &[UVec3::new(0, 1, 2)]);
+    }
+
+    #[test]
+    fn loading_errors() {
+        use crate::Error;
+        //a triangle in the xy plane without normals, with its primitive swapped in
+        let load = |primitive: &str| -> crate::error::Result<Mesh> {
+            let json = format!(
+                r#"{{
+                    "asset": {{ "version": "2.0" }},
+                    "meshes": [{{ "primitives": [{}] }}],
+                    "buffers": [{{ "byteLength": 44, "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAABAAIAAAA=" }}],
+                    "bufferViews": [{{ "buffer": 0, "byteLength": 36 }}, {{ "buffer": 0, "byteOffset": 36, "byteLength": 6 }}],
+                    "accessors": [
+                        {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] }},
+                        {{ "bufferView": 1, "componentType": 5123, "count": 3, "type": "SCALAR" }},
+                        {{ "bufferView": 0, "componentType": 5126, "count": 2, "type": "VEC3", "min": [0, 0, 0], "max": [1, 0, 0] }}
+                    ]
+                }}"#,
+                primitive
+            );
+            let (document, buffers, _) = gltf::import_slice(json.as_bytes())?;
+            Mesh::load_from_gltf(&document.meshes().next().unwrap(), &buffers)
+        };
+
+        //flat normals when the file has none
+        let mesh = load(r#"{ "attributes": { "POSITION": 0 }, "indices": 1 }"#).unwrap();
+        assert!(mesh.vertices().iter().all(|v| v.normal == glam::Vec3::Z));
+
+        //gltf's validation already catches the missing position
+        let missing_position = load(r#"{ "attributes": {}, "indices": 1 }"#).err().unwrap();
+        assert!(matches!(missing_position, Error::Gltf(_)) && missing_position.to_string().contains("POSITION"));
+        assert!(matches!(load(r#"{ "attributes": { "POSITION": 0 }, "mode": 1 }"#), Err(Error::Unsupported(_))));
+        assert!(matches!(load(r#"{ "attributes": { "POSITION": 2 }, "indices": 1 }"#), Err(Error::Invalid(_))));
+
+        let fixture = |name: &str| load_gltf(&std::path::Path::new("tests/fixtures").join(name));
+        let missing = fixture("missing.gltf").err().unwrap();
+        assert!(matches!(missing, Error::Io(_)));
+        assert!(missing.to_string().starts_with("couldn't read file"));
+        assert!(matches!(fixture("quad_albedo.png"), Err(Error::Gltf(_))));
+    }
+
+    #[test]
+    fn obj_loading() {
+        let path = std::path::Path::new("tests/fixtures/two_materials.obj");
+        let obj = crate::load_obj(path).unwrap();
+        let mesh = &obj.mesh;
+
+        //the quad is fanned into 2 triangles sharing its 4 corners
+        assert_eq!(mesh.vertices().len(), 7);
+        assert_eq!(
+            mesh.triangles(),
+            &[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3), UVec3::new(4, 5, 6)]
+        );
+        let sections = [
+            Section { triangles: 0..2, material: Some(0) },
+            Section { triangles: 2..3, material: Some(1) },
+        ];
+        assert_eq!(mesh.sections(), &sections);
+
+        let vertices = mesh.vertices();
+        assert_eq!(vertices[1].position, glam::vec4(1.0, -1.0, 0.0, 1.0));
+        assert_eq!(vertices[1].uv, glam::vec2(1.0, 1.0)); //v flipped
+        assert_eq!(vertices[1].color, glam::Vec3::ONE);
+        //negative indices, vertex colors and a flat normal for the corners that have none
+        assert_eq!(vertices[4].position, glam::vec4(2.0, 0.0, 0.0, 1.0));
+        assert_eq!(vertices[5].color, glam::vec3(0.0, 1.0, 0.0));
+        assert!(vertices[4..].iter().all(|v| v.normal == glam::Vec3::Z));
+
+        assert_eq!(obj.materials.len(), 2);
+        assert_eq!(obj.materials[0].name, "textured");
+        assert_eq!(obj.materials[0].diffuse, glam::Vec3::splat(0.5));
+        assert_eq!(
+            obj.materials[0].diffuse_texture.as_deref(),
+            Some(std::path::Path::new("tests/fixtures/quad_albedo.png"))
+        );
+        assert_eq!(obj.materials[1].alpha, 0.5);
+
+        let scene = crate::load_scene(path).unwrap();
+        let primitives = &scene.meshes[0];
+        assert_eq!(primitives.len(), 2);
+        assert_eq!(primitives[0].0.vertices().len(), 4);
+        assert_eq!(primitives[0].1.base_color_texture.as_ref().unwrap().width, 2);
+        assert_eq!(primitives[1].0.triangles(), &[UVec3::new(0, 1, 2)]);
+        assert_eq!(primitives[1].1.base_color_factor, glam::vec4(0.2, 0.4, 1.0, 0.5));
+        assert_eq!(primitives[1].0.alpha_mode(), AlphaMode::Blend);
+
+        let unsupported = crate::load_scene(std::path::Path::new("mesh.fbx")).err().unwrap();
+        assert_eq!(unsupported.to_string(), "unsupported file type \"fbx\"");
+        let broken = std::env::temp_dir().join("hello_triangle_broken.obj");
+        std::fs::write(&broken, "v 0 0 0\nv 1 0 0\nf 1 2 3\n").unwrap();
+        let error = crate::load_obj(&broken).err().unwrap().to_string();
+        assert!(error.ends_with("line 3: bad face index"), "{}", error);
+
+        std::fs::write(&broken, "mtllib two_materials.mtl\nusemtl glas\n").unwrap();
+        std::fs::copy("tests/fixtures/two_materials.mtl", std::env::temp_dir().join("two_materials.mtl")).unwrap();
+        let error = crate::load_obj(&broken).err().unwrap().to_string();
+        assert!(error.ends_with("line 2: no material named \"glas\""), "{}", error);
+
+        //texture options are skipped and file names can have spaces
+        let mtl = std::env::temp_dir().join("hello_triangle_options.mtl");
+        std::fs::write(&mtl, "newmtl a\nmap_Kd -o 0.5 -mm 0 1 -clamp on my texture.png\nnewmtl b\nmap_Kd -s 2 2 x.png\n").unwrap();
+        let materials = crate::load_mtl(&mtl).unwrap();
+        assert_eq!(materials[0].diffuse_texture, Some(std::env::temp_dir().join("my texture.png")));
+        assert_eq!(materials[1].diffuse_texture, Some(std::env::temp_dir().join("x.png")));
+    }
+
+    #[test]
+    fn stl_loading() {
+        let fixture = |name: &str| std::path::Path::new("tests/fixtures").join(name);
+        let ascii = crate::load_stl(&fixture("wedge.stl")).unwrap();
+        //binary, even though its header starts with solid
+        let binary = crate::load_stl(&fixture("wedge_binary.stl")).unwrap();
+        //same file with junk after the last facet
+        let padded = crate::load_stl(&fixture("wedge_padded.stl")).unwrap();
+
+        for mesh in [&ascii, &binary, &padded] {
+            //facets don't share vertices, each one gets its own flat normal
+            assert_eq!(mesh.vertices().len(), 6);
+            assert_eq!(mesh.triangles(), &[UVec3::new(0, 1, 2), UVec3::new(3, 4, 5)]);
+            assert_eq!(mesh.vertices()[5].position, glam::vec4(0.0, 0.0, 1.0, 1.0));
+            assert!(mesh.vertices()[..3].iter().all(|v| v.normal == glam::Vec3::Z));
+            assert!(mesh.vertices()[3..].iter().all(|v| v.normal == glam::Vec3::X));
+            assert!(mesh.vertices().iter().all(|v| v.color == glam::Vec3::ONE));
+        }
+        assert!(matches!(crate::load_stl(&fixture("quad_albedo.png")), Err(crate::Error::Invalid(_))));
+
+        //a facet short of a vertex or a file cut off halfway is an error, not a smaller mesh
+        for (name, what) in [("wedge_missing_vertex.stl", "facet without exactly 3 vertices"), ("wedge_truncated.stl", "file ends inside a facet")] {
+            let error = crate::load_stl(&fixture(name)).err().unwrap().to_string();
+            assert!(error.ends_with(what), "{}", error);
+        }
+    }
+
+    #[test]
+    fn ply_loading() {
+        let fixture = |name: &str| std::path::Path::new("tests/fixtures").join(name);
+        let mesh = crate::load_ply(&fixture("colored_quad.ply")).unwrap();
+        assert_eq!(mesh.vertices().len(), 5);
+        assert_eq!(mesh.triangles(), &[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3), UVec3::new(0, 3, 4)]);
+        let vertices = mesh.vertices();
+        assert_eq!(vertices[1].color, glam::vec3(0.0, 1.0, 0.0));
+        assert!((vertices[4].color - glam::vec3(0.2, 0.4, 0.6)).abs().max_element() < 1e-6);
+        assert_eq!(vertices[2].uv, glam::vec2(1.0, 0.0)); //t flipped
+        //no normals in the file, shared vertices average the faces around them
+        assert!((vertices[0].normal - glam::vec3(1.0, 0.0, 2.0).normalize()).abs().max_element() < 1e-6);
+        assert_eq!(vertices[1].normal, glam::Vec3::Z);
+        assert_eq!(vertices[4].normal, glam::Vec3::X);
+
+        //big endian, float colors, a scalar before the face list and an element that isn't used
+        let mesh = crate::load_ply(&fixture("quad_binary.ply")).unwrap();
+        assert_eq!(mesh.triangles(), &[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)]);
+        assert_eq!(mesh.vertices()[2].position, glam::vec4(1.0, 1.0, 0.0, 1.0));
+        assert_eq!(mesh.vertices()[2].color, glam::vec3(0.0, 0.0, 1.0));
+        assert!(mesh.vertices().iter().all(|v| v.normal == glam::Vec3::Z));
+
+        //normals on faces don't count, the vertices get averaged ones
+        let mesh = crate::load_ply(&fixture("face_normals.ply")).unwrap();
+        assert!(mesh.vertices().iter().all(|v| v.normal == glam::Vec3::Z));
+
+        //a comment mentioning end_header doesn't end the header
+        let mesh = crate::load_ply(&fixture("end_header_comment.ply")).unwrap();
+        assert_eq!(mesh.triangles(), &[UVec3::new(0, 1, 2)]);
+        assert_eq!(mesh.vertices()[1].position, glam::vec4(1.0, 0.0, 0.0, 1.0));
+
+        let scene = crate::load_scene(&fixture("quad_binary.ply")).unwrap();
+        assert_eq!(scene.draws().len(), 1);
+        assert!(matches!(crate::load_ply(&fixture("wedge.stl")), Err(crate::Error::Invalid(_))));
+    }
+
+    #[test]
+    fn netpbm_export() {
+        let dir = std::env::temp_dir();
+        let (color, depth) = ([to_argb8(255, 1, 2, 3)], [0.5]);
+        let header = |name: &str| std::fs::read(dir.join(name)).unwrap()[..2].to_vec();
+
+        crate::write_color(&dir.join("export_color.ppm"), &color, 1, 1).unwrap();
+        assert_eq!(header("export_color.ppm"), b"P6");
+        crate::write_depth(&dir.join("export_depth.pgm"), &depth, 1, 1, false).unwrap();
+        assert_eq!(header("export_depth.pgm"), b"P5");
+
+        //color doesn't go into a grayscale format and depth doesn't go into a color one
+        assert!(crate::write_color(&dir.join("export_color.pgm"), &color, 1, 1).is_err());
+        assert!(crate::write_depth(&dir.join("export_depth.ppm"), &depth, 1, 1, false).is_err());
+    }
+
+    #[test]
+    fn trilinear_minification() {
+        //a fine checkerboard squeezed onto a few pixels averages out instead of aliasing
+        let vertex = |x: f32, y: f32| Vertex::Construct(glam::vec4(x, y, 0.5, 1.0), glam::Vec3::Z, glam::Vec3::ONE, glam::vec2(x, y) * 0.5 + 0.5);
+        let mesh = Mesh::from_vertices(
+            &[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)],
+            &[vertex(-1.0, -1.0), vertex(1.0, -1.0), vertex(1.0, 1.0), vertex(-1.0, 1.0)],
+        );
+        let mut texture = checker(60, 60);
+
+        let mut render = |filter| {
+            texture.sampler.filter = filter;
+            let shader = DefaultShader::new(&glam::Mat4::IDENTITY, &glam::Mat4::IDENTITY, Some(&texture));
+            let mut target = RenderTarget::new(8, 8);
+            raster_mesh(&mesh, &shader, &mut target, &RasterState::default());
+            target.color
+        };
+        let nearest = render(Filter::Nearest);
+        let trilinear = render(Filter::Trilinear);
+        let spread = |colors: &[u32]| {
+            let reds = colors.iter().map(|&c| from_argb8(c).1);
+            reds.clone().max().unwrap() - reds.min().unwrap()
+        };
+        assert!(spread(&nearest) > 100);
+        assert!(spread(&trilinear) <= 2, "{:?}", trilinear);
+    }
+
+    #[test]
+    fn fill_rule_no_overdraw() {
+        //grid of triangles covering the whole viewport with no gaps or overlaps. Every second cell is split
+        //the other way and wound the other way round, shared edges run in all directions
+        let (width, height) = (37, 23);
+        let viewport = Rect::new(0, 0, width, height);
+        let cells = 9;
+        let vertex = |i: usize, j: usize| {
+            //inner vertices jittered, some land on pixel centers and sub pixel grid points exactly
+            let jitter = |k: usize| if k == 0 || k == cells { 0.0 } else { ((k * 7 + i * j * 3) % 5) as f32 * 0.031 - 0.06 };
+            let x = i as f32 / cells as f32 * 2.0 - 1.0 + jitter(i);
+            let y = j as f32 / cells as f32 * 2.0 - 1.0 + jitter(j);
+            let x = if i == 4 { ((i * width / cells) as f32 + 0.5) / width as f32 * 2.0 - 1.0 } else { x };
+            Vertex {
+                position: glam::vec4(x, y, 0.5, 1.0),
+                ..Default::default()
+            }
+        };
+
+        let mut tris = Vec::new();
+        for j in 0..cells {
+            for i in 0..cells {
+                let (a, b, c, d) = (vertex(i, j), vertex(i + 1, j), vertex(i + 1, j + 1), vertex(i, j + 1));
+                if (i + j) % 2 == 0 {
+                    tris.push(Triangle::Construct(a, b, c));
+                    tris.push(Triangle::Construct(a, c, d));
+                } else {
+                    tris.push(Triangle::Construct(a, d, b));
+                    tris.push(Triangle::Construct(b, d, c));
+                }
+            }
+        }
+
+        let bounds = viewport.bounds().unwrap();
+        let mut coverage = vec![0; width * height];
+        for tri in &tris {
+            let screen_tri = ScreenTriangle::new(tri, &viewport);
+            if let Some(bb) = triangle_screen_bounding_box(&screen_tri.screen, &bounds) {
+                for y in bb.top as usize..=bb.bot as usize {
+                    for x in bb.left as usize..=bb.right as usize {
+                        if screen_tri.edges.barycentric(x, y).is_some() {
+                            coverage[coords_to_index(x, y, width)] += 1;
+                        }
+                    }
+                }
+            }
+        }
+
+        assert!(coverage.iter().all(|&count| count == 1), "{:?}", coverage);
+    }
+
+    #[test]
+    fn stepped_coverage_matches_direct() {
+        //coverage4 steps the weights instead of evaluating them, it has to agree with evaluating every pixel on its own
+        let viewport = Rect::new(0, 0, 61, 47);
+        let mesh = sphere(7, 9);
+        let mvp = glam::Mat4::perspective_rh(1.0, 61.0 / 47.0, 0.1, 10.0)
+            * glam::Mat4::from_translation(glam::vec3(0.1, -0.2, -2.2));
+
+        for tri in mesh.triangles() {
+            let [a, b, c] = mesh.get_vertices_from_triangle(*tri).map(|v| Vertex { position: mvp * v.position, ..*v });
+            let screen_tri = ScreenTriangle::new(&Triangle::Construct(a, b, c), &viewport);
+            let edges = &screen_tri.edges;
+
+            let mut row = edges.weights(FixedPoint::pixel_center(0, 0));
+            for y in 0..47 {
+                let mut weights = row;
+                for x in (0..61).step_by(4) {
+                    let mask = edges.coverage4(&weights);
+                    for k in 0..4.min(61 - x) {
+                        assert_eq!(mask & (1 << k) != 0, edges.barycentric(x + k, y).is_some());
+                    }
+                    weights = EdgeSetup::step(&weights, &edges.step_x.map(|s| s * 4));
+                }
+                row = EdgeSetup::step(&row, &edges.step_y);
+            }
+        }
+    }
+
+    #[test]
+    fn backface_culling() {
+        //counter clockwise in NDC, y points up there but down on screen
+        let vertex = |x: f32, y: f32| Vertex {
+            position: glam::vec4(x, y, 0.5, 1.0),
+            ..Default::default()
+        };
+        let tri = Triangle::Construct(vertex(-0.5, -0.5), vertex(0.5, -0.5), vertex(0.0, 0.5));
+        let viewport = Rect::new(0, 0, 100, 100);
+        let ccw = ScreenTriangle::new(&tri, &viewport).area;
+        let cw = ScreenTriangle::new(&tri.reorder(VerticesOrder::ACB), &viewport).area;
+
+        assert!(!cull_triangle_backface(ccw, CullMode::Back, FrontFace::CounterClockwise));
+        assert!(cull_triangle_backface(cw, CullMode::Back, FrontFace::CounterClockwise));
+        assert!(cull_triangle_backface(ccw, CullMode::Front, FrontFace::CounterClockwise));
+        assert!(cull_triangle_backface(ccw, CullMode::Back, FrontFace::Clockwise));
+        assert!(!cull_triangle_backface(cw, CullMode::Back, FrontFace::Clockwise));
+        assert!(!cull_triangle_backface(cw, CullMode::None, FrontFace::CounterClockwise));
+        assert!(cull_triangle_backface(0.0, CullMode::None, FrontFace::CounterClockwise));
+    }
 }
 
-fn clip_triangle_one(tri: &Triangle) -> Triangle {
-    let alpha_a = (-tri.vert0.position.z) / (tri.vert2.position.z - tri.vert0.position.z);
-    let alpha_b = (-tri.vert1.position.z) / (tri.vert2.position.z - tri.vert1.position.z);
+//shades every pixel of tri inside bb. bb must lie within the rows owned by target.
+//alpha_mode is the one of the mesh tri belongs to.
+//always inlined, the tiled renderer loses about a third of its speed to the call otherwise
+#[inline(always)]
+pub fn raster_screen_triangle<V, S>(
+    tri: &ScreenTriangle<V>,
+    bb: &BoundingBox2D,
+    target: &mut TargetSlice,
+    shader: &S,
+    state: &RasterState,
+    alpha_mode: AlphaMode)
+where
+    V: Varying,
+    S: FragmentShader<V> + ?Sized,
+{
+    let [ndc0, ndc1, ndc2] = tri.ndc;
+    let edges = &tri.edges;
+
+    //plain wireframe only fills depth so hidden edges get rejected
+    let depth_only = matches!(state.render_type, RenderType::Wireframe { overlay: false });
+    let (blend, depth_state, stencil) = (state.blend, state.depth, &state.stencil);
+    let reverse_z = target.reverse_z;
 
-    //interpolate vertices 
-    let mut v0 = Lerp(tri.vert0, tri.vert2, alpha_a);
-    let mut v1 = Lerp(tri.vert1, tri.vert2, alpha_b);
-    let mut v2 = tri.vert2;
+    //edge functions get evaluated once at the top left pixel, then stepped
+    let (left, right) = (bb.left as usize, bb.right as usize);
+    let mut row = edges.weights(FixedPoint::pixel_center(left, bb.top as usize));
 
-    let red = Vec3::new(1.0, 0.0, 0.0);
+    if target.samples > 1 {
+        raster_screen_triangle_msaa(tri, bb, target, row, shader, state, alpha_mode);
+        return;
+    }
+
+    let mut shade = |x: usize, y: usize, weights: &[i64; 3]| {
+        let pixel_id = target.index(x, y);
+        if !stencil_test(&mut target.stencil[pixel_id], stencil) {
+            return;
+        }
+        let bary = edges.to_barycentric(weights);
+        let depth = bary.x * ndc0.z + bary.y * ndc1.z + bary.z * ndc2.z;
+        if !depth_state.compare.test(depth, target.depth[pixel_id]) {
+            update_stencil(&mut target.stencil[pixel_id], stencil, |s| s.depth_fail);
+            return;
+        }
+        if depth_only {
+            if depth_state.write {
+                target.depth[pixel_id] = depth;
+            }
+            update_stencil(&mut target.stencil[pixel_id], stencil, |s| s.pass);
+            return;
+        }
+        let depth_gray = display_depth(depth, reverse_z);
+        if let Some(color) = fragment_color(tri, shader, state, alpha_mode, bary, depth_gray) {
+            if depth_state.write {
+                target.depth[pixel_id] = depth;
+            }
+            target.color[pixel_id] = blend.apply(color, target.color[pixel_id]); //write to buffer
+            update_stencil(&mut target.stencil[pixel_id], stencil, |s| s.pass);
+        }
+    };
+
+    //coverage is tested 4 pixels at a time, see EdgeSetup::coverage4
+    let step_x4 = edges.step_x.map(|s| s * 4);
+    for y in (bb.top as usize)..=bb.bot as usize {
+        let mut weights = row;
+        for x in (left..=right).step_by(4) {
+            let mask = edges.coverage4(&weights);
+            if mask != 0 {
+                let mut lane = weights;
+                for k in 0..4.min(right + 1 - x) {
+                    if mask & (1 << k) != 0 {
+                        shade(x + k, y, &lane);
+                    }
+                    lane = EdgeSetup::step(&lane, &edges.step_x);
+                }
+            }
+            weights = EdgeSetup::step(&weights, &step_x4);
+        }
+        row = EdgeSetup::step(&row, &edges.step_y);
+    }
+}
 
-    v0.color = red;
-    v1.color = red;
-    v2.color = red;
+//runs the stencil test against stored and applies the fail op when it doesn't pass. Always passes without a stencil state
+#[inline(always)]
+fn stencil_test(stored: &mut u8, stencil: &Option<StencilState>) -> bool {
+    match stencil {
+        Some(stencil) if !stencil.test(*stored) => {
+            *stored = stencil.apply(stencil.fail, *stored);
+            false
+        }
+        _ => true,
+    }
+}
 
-    //if Triangle.vert0.. was called v0.. you would not need to explicitly assign v0.. to vert0.. 
-    Triangle {vert0 : v0, vert1 : v1, vert2 : v2}
+#[inline(always)]
+fn update_stencil(stored: &mut u8, stencil: &Option<StencilState>, op: fn(&StencilState) -> StencilOp) {
+    if let Some(stencil) = stencil {
+        *stored = stencil.apply(op(stencil), *stored);
+    }
 }
 
-pub fn clip_cull_triangle(tri: &Triangle) -> ClipResult {
-    if cull_triangle_backface(tri) {
-        //triangle gets culled
-        return ClipResult::None;    //why does this have to be an explicit return statement?
+//rgba of the pixel with barycentrics bary, None if the shader or the alpha mode discarded it.
+//depth_gray is what RenderType::Depth shows, see export::display_depth
+#[inline(always)]
+fn fragment_color<V, S>(
+    tri: &ScreenTriangle<V>,
+    shader: &S,
+    state: &RasterState,
+    alpha_mode: AlphaMode,
+    bary: Vec3,
+    depth_gray: f32,
+) -> Option<Vec4>
+where
+    V: Varying,
+    S: FragmentShader<V> + ?Sized,
+{
+    if let RenderType::Depth = state.render_type {
+        return Some(Vec3::splat(depth_gray).extend(1.0));
     }
-    if cull_triangle_view_frustum(tri) {
-        ClipResult::None
+
+    let attributes = tri.interpolate(bary);
+    let shaded = if shader.uses_derivatives() {
+        //forward differences to the next pixel right and down, the barycentrics step linearly in screen space
+        let edges = &tri.edges;
+        let derivatives = Derivatives {
+            ddx: tri.interpolate(bary + edges.to_barycentric(&edges.step_x)) - attributes,
+            ddy: tri.interpolate(bary + edges.to_barycentric(&edges.step_y)) - attributes,
+        };
+        shader.fragment_with_derivatives(&attributes, &derivatives)?
     } else {
-        if tri.vert0.position.z < 0.0 {
+        shader.fragment(&attributes)?
+    };
 
-            if tri.vert1.position.z < 0.0 {
-                ClipResult::One(clip_triangle_one(tri))
-            } else if tri.vert2.position.z < 0.0 {
-                ClipResult::One(clip_triangle_one(&tri.reorder(VerticesOrder::ACB)))
-            } else {
-                ClipResult::Two(clip_triangle_two(&tri.reorder(VerticesOrder::ACB)))
+    let color = match state.render_type {
+        RenderType::ClipDebug => clip_debug_color(tri.clip_piece, shaded.truncate()).extend(shaded.w),
+        _ => shaded,
+    };
+    let alpha = alpha_mode.apply(color.w)?;
+    Some(color.truncate().extend(alpha))
+}
+
+//multisampled version of the loop above. Coverage and depth are per sample, the fragment color is
+//computed once at the pixel center and blended into every sample that passed
+#[inline(never)]
+fn raster_screen_triangle_msaa<V, S>(
+    tri: &ScreenTriangle<V>,
+    bb: &BoundingBox2D,
+    target: &mut TargetSlice,
+    mut row: [i64; 3],
+    shader: &S,
+    state: &RasterState,
+    alpha_mode: AlphaMode,
+) where
+    V: Varying,
+    S: FragmentShader<V> + ?Sized,
+{
+    let [ndc0, ndc1, ndc2] = tri.ndc;
+    let depth_only = matches!(state.render_type, RenderType::Wireframe { overlay: false });
+    let edges = &tri.edges;
+    let offsets = sample_offsets(target.samples);
+
+    for y in (bb.top as usize)..=bb.bot as usize {
+        let mut weights = row;
+        for x in (bb.left as usize)..=bb.right as usize {
+            let pixel_id = target.index(x, y);
+
+            let mut passed = [None; 8];
+            for (s, &offset) in offsets.iter().enumerate() {
+                let sample = edges.sample_weights(&weights, offset);
+                if edges.covers(&sample) && stencil_test(&mut target.stencil[pixel_id + s], &state.stencil) {
+                    let bary = edges.to_barycentric(&sample);
+                    let depth = bary.x * ndc0.z + bary.y * ndc1.z + bary.z * ndc2.z;
+                    if state.depth.compare.test(depth, target.depth[pixel_id + s]) {
+                        passed[s] = Some(depth);
+                    } else {
+                        update_stencil(&mut target.stencil[pixel_id + s], &state.stencil, |s| s.depth_fail);
+                    }
+                }
             }
 
-        } else if tri.vert1.position.z < 0.0 {
-            
-            if tri.vert2.position.z < 0.0 {
-                ClipResult::One(clip_triangle_one(&tri.reorder(VerticesOrder::BCA)))
+            let color = if !passed.iter().any(Option::is_some) {
+                None
+            } else if depth_only {
+                Some(None)
             } else {
-                ClipResult::Two(clip_triangle_two(&tri.reorder(VerticesOrder::BAC)))
+                let bary = edges.to_barycentric(&weights);
+                let depth = bary.x * ndc0.z + bary.y * ndc1.z + bary.z * ndc2.z;
+                fragment_color(tri, shader, state, alpha_mode, bary, display_depth(depth, target.reverse_z)).map(Some)
+            };
+
+            //None if nothing passed or the pixel was discarded, Some(None) when only depth gets written
+            if let Some(color) = color {
+                for (s, depth) in passed.iter().enumerate() {
+                    if let Some(depth) = *depth {
+                        if state.depth.write {
+                            target.depth[pixel_id + s] = depth;
+                        }
+                        if let Some(color) = color {
+                            target.color[pixel_id + s] = state.blend.apply(color, target.color[pixel_id + s]);
+                        }
+                        update_stencil(&mut target.stencil[pixel_id + s], &state.stencil, |s| s.pass);
+                    }
+                }
             }
+            weights = EdgeSetup::step(&weights, &edges.step_x);
+        }
+        row = EdgeSetup::step(&row, &edges.step_y);
+    }
+}
+
+//draws the three edges of tri, depth tested against what is already in target with a little slack, see CompareFunction::test_biased.
+//only pixels inside bounds are touched, edges are clipped to draw_bounds first so the same pixels come out no matter how the screen is split up
+pub fn raster_screen_triangle_edges<V>(
+    tri: &ScreenTriangle<V>,
+    bounds: &BoundingBox2D,
+    target: &mut TargetSlice,
+    draw_bounds: &BoundingBox2D,
+    depth_state: &DepthState)
+{
+    //a hair short of the last pixel's far side, bresenham truncates to whole pixels
+    let viewport = BoundingBox2D {
+        right: draw_bounds.right + 0.999,
+        bot: draw_bounds.bot + 0.999,
+        ..*draw_bounds
+    };
 
-        } else if tri.vert2.position.z < 0.0 {
-            ClipResult::Two(clip_triangle_two(&tri.reorder(VerticesOrder::CBA)))
-        } else {
-            ClipResult::One(*tri)
+    for (a, b) in [(0, 1), (1, 2), (2, 0)] {
+        let (p0, p1) = (tri.screen[a], tri.screen[b]);
+        let (z0, z1) = (tri.ndc[a].z, tri.ndc[b].z);
+
+        if let Some((t0, t1)) = clip_line(p0, p1, &viewport) {
+            let edge = p1 - p0;
+            let length_sq = edge.length_squared();
+
+            for point in bresenham_function(Lerp(p0, p1, t0), Lerp(p0, p1, t1)) {
+                let (x, y) = (point.x as f32, point.y as f32);
+                if x < bounds.left || x > bounds.right || y < bounds.top || y > bounds.bot {
+                    continue;
+                }
+
+                //ndc z is linear in screen space, so project the pixel onto the edge to find its depth
+                let t = if length_sq > 0.0 {
+                    ((glam::vec2(x, y) + 0.5 - p0).dot(edge) / length_sq).clamp(0.0, 1.0)
+                } else {
+                    0.0
+                };
+                let depth = Lerp(z0, z1, t);
+
+                let pixel_id = target.index(point.x as usize, point.y as usize);
+                for s in pixel_id..pixel_id + target.samples {
+                    if depth_state.compare.test_biased(depth, target.depth[s], WIREFRAME_DEPTH_BIAS) {
+                        target.color[s] = WIREFRAME_COLOR;
+                    }
+                }
+            }
         }
     }
 }
 
-pub fn raster_mesh(
+pub fn raster_mesh<S>(
     mesh: &Mesh,
-    loc_mat: &Mat4,
-    mvp: &Mat4,
-    texture: Option<&Texture>,
-    buffer: &mut Vec<u32>,
-    z_buffer: &mut Vec<f32>,
-    viewport_size: Vec2,
-    render_type: &RenderType,
-) {
-    for tri in mesh.triangles() {
-        let vertices = mesh.get_vertices_from_triangle(*tri);
+    shader: &S,
+    target: &mut RenderTarget,
+    state: &RasterState,
+) -> ClipStats
+where
+    S: VertexShader + FragmentShader<S::Varying> + ?Sized,
+{
+    let render_type = &state.render_type;
+    let viewport = target.viewport;
+    //viewport and scissor entirely off the target, nothing can be drawn
+    let Some(draw_bounds) = target.draw_bounds() else {
+        return ClipStats::default();
+    };
+    let mut slice = target.slice();
+    let mut edges = Vec::new();
 
-        //AABB on vertices to sort into tiles
+    let stats = for_each_screen_triangle(mesh, shader, &viewport, state, |tri| {
+        if let Some(bb) = triangle_screen_bounding_box(&tri.screen, &draw_bounds) {
+            raster_screen_triangle(tri, &bb, &mut slice, shader, state, mesh.alpha_mode());
+        }
+        if let RenderType::Wireframe { .. } = render_type {
+            edges.push(*tri);
+        }
+    });
 
-        let tempTri = &Triangle {    //what the fuck?
-            vert0: *vertices[0],
-            vert1: *vertices[1],
-            vert2: *vertices[2],
-        };
+    //edges go on top once all the depth is in
+    for tri in &edges {
+        raster_screen_triangle_edges(tri, &draw_bounds, &mut slice, &draw_bounds, &state.depth);
+    }
+
+    stats
+}
 
-        Raster_Triangle(
-            tempTri, 
-            loc_mat,
-            mvp, 
-            texture, 
-            buffer, 
-            z_buffer, 
-            viewport_size, 
-            render_type
+//runs the vertex shader over mesh, clips every triangle and sets up what is left in screen space.
+//backfaces are culled here since the winding is only known after the perspective divide
+fn for_each_screen_triangle<S, F>(
+    mesh: &Mesh,
+    shader: &S,
+    viewport: &Rect,
+    state: &RasterState,
+    mut f: F,
+) -> ClipStats
+where
+    S: VertexShader + ?Sized,
+    F: FnMut(&ScreenTriangle<S::Varying>),
+{
+    //shared vertices only go through the shader once
+    let clip_vertices: Vec<S::Varying> = mesh.vertices().iter().map(|v| shader.vertex(v)).collect();
+    let cull_mode = if mesh.double_sided() { CullMode::None } else { state.cull_mode };
+    let mut stats = ClipStats::default();
+
+    //piece is which part of a clipped triangle we are looking at, None if the triangle didn't need clipping
+    let mut emit = |tri: &Triangle<S::Varying>, piece: Option<usize>| -> bool {
+        let mut screen_tri = ScreenTriangle::new(tri, viewport);
+        screen_tri.clip_piece = piece;
+        if cull_triangle_backface(screen_tri.area, cull_mode, state.front_face) {
+            return false;
+        }
+        f(&screen_tri);
+        true
+    };
+
+    for tri in mesh.triangles() {
+        let tri = Triangle::Construct(
+            clip_vertices[tri.x as usize],
+            clip_vertices[tri.y as usize],
+            clip_vertices[tri.z as usize],
         );
+
+        match clip_cull_triangle(&tri, state.guard_band) {
+            ClipResult::None => stats.culled += 1,
+            ClipResult::One(ctri) => {
+                if !emit(&ctri, None) {
+                    stats.culled += 1;
+                }
+            }
+            ClipResult::Fan(ctris) => {
+                let mut drawn = false;
+                for (piece, ctri) in ctris.iter().enumerate() {
+                    drawn |= emit(ctri, Some(piece));
+                }
+                if drawn {
+                    stats.clipped += 1;
+                } else {
+                    stats.culled += 1;
+                }
+            }
+        }
     }
+
+    stats
+}
+
+//RenderType::ClipDebug tints pieces of clipped triangles red, green and blue, everything else grey.
+//the shaded brightness is kept so the shape stays readable
+fn clip_debug_color(piece: Option<usize>, shaded: Vec3) -> Vec3 {
+    let tint = piece.map_or(CLIP_DEBUG_UNCLIPPED, |piece| CLIP_DEBUG_COLORS[piece % CLIP_DEBUG_COLORS.len()]);
+    let luminance = shaded.dot(glam::vec3(0.2126, 0.7152, 0.0722));
+    tint * (0.25 + 0.75 * luminance)
+}
+
+//same result as raster_mesh, but triangles are binned into tiles first and the tiles are shaded on multiple threads
+pub fn raster_mesh_tiled<S>(
+    mesh: &Mesh,
+    shader: &S,
+    target: &mut RenderTarget,
+    state: &RasterState,
+) -> ClipStats
+where
+    S: VertexShader + FragmentShader<S::Varying> + Sync + ?Sized,
+    S::Varying: Sync,
+{
+    let render_type = &state.render_type;
+    let alpha_mode = mesh.alpha_mode();
+    let Some(draw_bounds) = target.draw_bounds() else {
+        return ClipStats::default();
+    };
+    let mut bins = TileBins::new(target.size());
+    let mut screen_tris: Vec<(ScreenTriangle<S::Varying>, BoundingBox2D)> = Vec::new();
+
+    let stats = for_each_screen_triangle(mesh, shader, &target.viewport, state, |tri| {
+        if let Some(bb) = triangle_screen_bounding_box(&tri.screen, &draw_bounds) {
+            bins.bin(screen_tris.len(), &bb);
+            screen_tris.push((*tri, bb));
+        }
+    });
+
+    raster_tiles(&bins, target, |tile_bb, ids, target| {
+        for &id in ids {
+            let (tri, bb) = &screen_tris[id];
+            if let Some(bb) = bb.intersect(tile_bb) {
+                raster_screen_triangle(tri, &bb, target, shader, state, alpha_mode);
+            }
+        }
+
+        if let RenderType::Wireframe { .. } = render_type {
+            for &id in ids {
+                raster_screen_triangle_edges(&screen_tris[id].0, tile_bb, target, &draw_bounds, &state.depth);
+            }
+        }
+    });
+
+    stats
 }
 
+//draws opaque and masked meshes first, then the AlphaMode::Blend ones back to front with state.blend, alpha blending
+//if that is Replace, and without depth writes, so transparent meshes show everything behind them without hiding each other.
+//meshes are sorted by the clip space w of their center, which is the view distance for a perspective projection
+pub fn raster_meshes<S>(
+    meshes: &[(&Mesh, &S)],
+    target: &mut RenderTarget,
+    state: &RasterState,
+) -> ClipStats
+where
+    S: VertexShader + FragmentShader<S::Varying> + Sync + ?Sized,
+    S::Varying: Sync,
+{
+    let mut stats = ClipStats::default();
+    let (transparent, opaque): (Vec<_>, Vec<_>) =
+        meshes.iter().partition(|(mesh, _)| mesh.alpha_mode() == AlphaMode::Blend);
+
+    for (mesh, shader) in opaque {
+        stats += raster_mesh_tiled(mesh, shader, target, state);
+    }
+
+    let mut transparent: Vec<_> = transparent
+        .into_iter()
+        .map(|(mesh, shader)| {
+            let center = Vertex {
+                position: mesh.center().extend(1.0),
+                ..Default::default()
+            };
+            (shader.vertex(&center).position().w, mesh, shader)
+        })
+        .collect();
+    transparent.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+    //Replace would make them opaque, any other blend state was picked for them on purpose
+    let blend = if state.blend == BlendState::Replace { BlendState::Alpha } else { state.blend };
+    let transparent_state = RasterState {
+        blend,
+        depth: DepthState {
+            write: false,
+            ..state.depth
+        },
+        ..*state
+    };
+    for (_, mesh, shader) in transparent {
+        stats += raster_mesh_tiled(mesh, shader, target, &transparent_state);
+    }
+
+    stats
+}
+
+//a DefaultShader for every primitive the scene draws, model goes on top of the node transforms
+pub fn scene_shaders<'a>(scene: &'a Scene, model: &Mat4, camera: &Camera) -> Vec<(&'a Mesh, DefaultShader<'a>)> {
+    let view_projection = camera.projection() * camera.view();
+    scene
+        .draws()
+        .into_iter()
+        .map(|(mesh, material, world)| {
+            let model = *model * world;
+            (mesh, DefaultShader::from_material(&model, &(view_projection * model), material))
+        })
+        .collect()
+}
+
+//every node of the scene through raster_meshes, so transparent primitives get sorted across nodes
+pub fn raster_scene(
+    scene: &Scene,
+    model: &Mat4,
+    camera: &Camera,
+    target: &mut RenderTarget,
+    state: &RasterState,
+) -> ClipStats {
+    let shaders = scene_shaders(scene, model, camera);
+    let meshes: Vec<_> = shaders.iter().map(|(mesh, shader)| (*mesh, shader)).collect();
+    raster_meshes(&meshes, target, state)
+}
+
+//renders mesh into target and resolves it, without opening a window, for exporting with export::write_color/write_depth.
+//the model matrix goes in separately so normals get transformed right
+pub fn render_headless(
+    mesh: &Mesh,
+    texture: Option<&Texture>,
+    model: &Mat4,
+    camera: &Camera,
+    target: &mut RenderTarget,
+    state: &RasterState,
+) {
+    let shader = DefaultShader::new(model, &(camera.projection() * camera.view() * *model), texture);
+    raster_meshes(&[(mesh, &shader)], target, state);
+    target.resolve();
+}
+
+//bounds are inclusive whole pixels, see RenderTarget::draw_bounds
 pub fn triangle_screen_bounding_box(
     tri: &[Vec2; 3], //not triangle struct because this should be used with screen coordinates
-    viewport_size: Vec2,
+    bounds: &BoundingBox2D,
 ) -> Option<BoundingBox2D> {
     let bb = get_triangle_bounding_box_2d(tri);
 
     //just AABB
-    if bb.left >= viewport_size.x || bb.right < 0.0 || bb.bot >= viewport_size.y || bb.top < 0.0 {
+    if bb.left >= bounds.right + 1.0 || bb.right < bounds.left || bb.top >= bounds.bot + 1.0 || bb.bot < bounds.top {
         None
     } else {
-        let left = bb.left.max(0.0);
-        let right = bb.right.min(viewport_size.x - 1.0);
-        let top = bb.top.min(viewport_size.y - 1.0);
-        let bot = bb.bot.max(0.0);
+        //snapped to whole pixels so boxes can be intersected with tile bounds
+        let left = bb.left.max(bounds.left).floor();
+        let right = bb.right.min(bounds.right).floor();
+        let top = bb.top.max(bounds.top).floor();
+        let bot = bb.bot.min(bounds.bot).floor();
 
         Some(BoundingBox2D {
             left,
@@ -381,31 +1834,20 @@ pub fn triangle_screen_bounding_box(
     }
 }
 
-pub fn load_gltf(path: &Path) -> Mesh {
-    let (document, buffers, _images) = gltf::import(path).unwrap();
-
-    for scene in document.scenes() {
-        for node in scene.nodes() {
-            println!(
-                "Node #{} has {} children, camera: {:?}, mesh: {:?}, transform: {:?}",
-                node.index(),
-                node.children().count(),
-                node.camera(),
-                node.mesh().is_some(),
-                node.transform(),
-            );
-            println!(
-                "Node #{} has transform: trans {:?}, rot {:?}, scale {:?},",
-                node.index(),
-                node.transform().decomposed().0,
-                node.transform().decomposed().1,
-                node.transform().decomposed().2,
-            );
-            if let Some(mesh) = node.mesh() {
-                return Mesh::load_from_gltf(&mesh, &buffers); //why does this need an explicit return statement?
-            }
-        }
-    }
+//the default scene with every mesh, material and texture in the file
+pub fn load_gltf(path: &Path) -> error::Result<Scene> {
+    let (document, buffers, images) = gltf::import(path)?;
+    Scene::from_gltf(&document, &buffers, &images)
+}
 
-    Mesh::new()
-}
\ No newline at end of file
+//picks the loader by file extension
+pub fn load_scene(path: &Path) -> error::Result<Scene> {
+    let extension = path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    match extension.as_str() {
+        "gltf" | "glb" => load_gltf(path),
+        "obj" => load_obj(path)?.into_scene(),
+        "stl" => Ok(Scene::from_primitives(vec![(load_stl(path)?, Material::default())])),
+        "ply" => Ok(Scene::from_primitives(vec![(load_ply(path)?, Material::default())])),
+        _ => Err(Error::Unsupported(format!("file type \"{}\"", extension))),
+    }
+}
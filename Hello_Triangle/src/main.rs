@@ -1,6 +1,5 @@
 extern crate minifb;
 use std::path::Path;
-use std::thread;
 use std::time::Instant;
 use std::vec;
 
@@ -13,6 +12,7 @@ use Hello_Triangle::*;
 
 const WIDTH: usize = 480;
 const HEIGHT: usize = 480;
+const SAMPLES: SampleCount = SampleCount::X1;
 
 fn main() {
     let mut window = Window::new(
@@ -25,45 +25,15 @@ fn main() {
         panic!("{}", e);
     });
 
-    let tile_size_xy = 32;
-
-    if WIDTH % tile_size_xy != 0 || HEIGHT % tile_size_xy != 0 {
-        let err_info =
-            "Tile size is incompatible with set screen size. Needs to be wholly divisable";
-        //error!("Error: {}", err_info);
-        eprintln!("Error: {}", err_info);
-    }
-
     // Limit to max ~60 fps update rate
     window.limit_update_rate(Some(std::time::Duration::from_micros(16600)));
 
-    //thread builder, could build worker threads that rasterize specific cell
-    //can set stack size of thread through builder, so maybe max mem per pixel * 32^2 for a tile
-    //for 480x480 res you need 15 tiles
-
-    //spawn threads
-    let handle = thread::spawn(|| {
-        for i in 1..10 {
-            println!("Thread number from spawned threads {}", i);
-            thread::sleep(std::time::Duration::from_millis(1));
-        }
-    });
-
-    for i in 1..5 {
-        println!("number of threads from main thread {}", i);
-        thread::sleep(std::time::Duration::from_millis(1));
-    }
-    handle.join().unwrap();
-
     let mut delta_time = 0.0;
-    let mut buffer: Vec<u32> = vec![to_argb8(255, 0, 0, 0); WIDTH * HEIGHT];
-    let mut z_buffer = vec![f32::INFINITY; WIDTH * HEIGHT];
+    let mut target = RenderTarget::with_samples(WIDTH, HEIGHT, SAMPLES);
 
     let mut frame_times: Vec<f32> = vec![0.0; 60];
 
-    let mut rendering_type = RenderType::Std;
-
-    let window_size = glam::vec2(WIDTH as f32, HEIGHT as f32);
+    let mut raster_state = RasterState::default();
 
     let aspect_ratio = WIDTH as f32 / HEIGHT as f32;
 
@@ -76,21 +46,13 @@ fn main() {
     };
 
     //maybe multithread model loading, could be fun
-    let texture = Texture::Load(Path::new("Assets/Helmet/Default_albedo.jpg"));
-    let mesh = load_gltf(Path::new("Assets/Helmet/DamagedHelmet.gltf"));
-
-    let count = 0;
-    //let mut tiles: Vec<Tile> = vec![Tile::new(); buffer.iter().len() / 32];
-
-    //construct tiles
-    // for i in buffer.iter() {
-    //     let coords = index_to_coords(count, WIDTH);
-    //     if coords.0 % tile_size_xy == 0 && coords.1 % tile_size_xy == 0 {
-    //         //tiles.append(Tile {});
-    //         //spawn new thread for tile... how to manage?
-    //         let handle = thread::spawn(|| {});
-    //     }
-    // }
+    //the helmet unless another glTF, OBJ, STL or PLY file is passed in
+    let path = std::env::args().nth(1).unwrap_or_else(|| "Assets/Helmet/DamagedHelmet.gltf".to_string());
+    let path = Path::new(&path);
+    let scene = load_scene(path).unwrap_or_else(|e| {
+        eprintln!("couldn't load {}: {}", path.display(), e);
+        std::process::exit(1);
+    });
 
     let mut rot = 0.0;
 
@@ -109,8 +71,7 @@ fn main() {
             println!("Framerate: {:?} p/sec", (delta_time * 60.0));
         }
 
-        clear_buffer(&mut buffer, 0); //screen clear
-        clear_buffer(&mut z_buffer, f32::INFINITY);
+        target.clear(0, f32::INFINITY); //screen clear
 
         camera.transform = Transform::from_translation_rotation(
             camera.transform.translation,
@@ -118,9 +79,15 @@ fn main() {
         );
 
         if window.is_key_down(Key::Space) {
-            rendering_type = RenderType::Depth;
+            raster_state.render_type = RenderType::Depth;
+        } else if window.is_key_down(Key::W) {
+            raster_state.render_type = RenderType::Wireframe { overlay: false };
+        } else if window.is_key_down(Key::Q) {
+            raster_state.render_type = RenderType::Wireframe { overlay: true };
+        } else if window.is_key_down(Key::C) {
+            raster_state.render_type = RenderType::ClipDebug;
         } else {
-            rendering_type = RenderType::Std;
+            raster_state.render_type = RenderType::Std;
         }
 
         camera_input(&window, &mut camera, &delta_time);
@@ -139,27 +106,32 @@ fn main() {
             rot,
         ))
         .local();
-        let view = camera.view();
-        let proj = camera.projection();
-
-        //should prolly foreach mesh this
-        raster_mesh(
-            &mesh,
-            &parent_local,
-            &(proj * view * parent_local),
-            Some(&texture),
-            &mut buffer,
-            &mut z_buffer,
-            window_size,
-            &rendering_type,
-        );
+
+        //hold T to compare against the single threaded path, it ignores the transparent pass ordering
+        let clip_stats = if window.is_key_down(Key::T) {
+            let mut stats = ClipStats::default();
+            for (mesh, shader) in scene_shaders(&scene, &parent_local, &camera) {
+                stats += raster_mesh(mesh, &shader, &mut target, &raster_state);
+            }
+            stats
+        } else {
+            raster_scene(&scene, &parent_local, &camera, &mut target, &raster_state)
+        };
+
+        if let RenderType::ClipDebug = raster_state.render_type {
+            println!(
+                "Culled: {} triangles, clipped: {} triangles",
+                clip_stats.culled, clip_stats.clipped
+            );
+        }
+
+        target.resolve();
 
         rot += 0.6 * delta_time;
 
         // We unwrap here as we want this code to exit if it fails. Real applications may want to handle this in a different way
-        window.update_with_buffer(&buffer, WIDTH, HEIGHT).unwrap();
+        window.update_with_buffer(&target.color, WIDTH, HEIGHT).unwrap();
 
-        count + 1;
     }
 }
 
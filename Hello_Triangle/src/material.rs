@@ -0,0 +1,61 @@
+use crate::blend::AlphaMode;
+use crate::sampler::Sampler;
+use crate::texture::{Texture, TextureError};
+use glam::Vec4;
+use std::sync::Arc;
+
+//how a primitive's surface looks, the parts of a glTF pbr material DefaultShader knows what to do with.
+//textures are shared between every material that uses them
+#[derive(Clone)]
+pub struct Material {
+    pub base_color_texture: Option<Arc<Texture>>,
+    pub base_color_factor: Vec4, //rgba, multiplies the texture or stands in for it
+    pub tex_coord: u32,          //which TEXCOORD_n set the texture is read with
+    pub alpha_mode: AlphaMode,
+    pub double_sided: bool,
+}
+
+impl Material {
+    //textures is one per document texture, see load_gltf_textures
+    pub fn from_gltf(material: &gltf::Material, textures: &[Arc<Texture>]) -> Self {
+        let pbr = material.pbr_metallic_roughness();
+        let base_color = pbr.base_color_texture();
+        Self {
+            base_color_texture: base_color
+                .as_ref()
+                .and_then(|info| textures.get(info.texture().index()).cloned()),
+            base_color_factor: Vec4::from(pbr.base_color_factor()),
+            tex_coord: base_color.map_or(0, |info| info.tex_coord()),
+            alpha_mode: AlphaMode::from_gltf(material),
+            double_sided: material.double_sided(),
+        }
+    }
+}
+
+//same as glTF's default material, white and opaque
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            base_color_texture: None,
+            base_color_factor: Vec4::ONE,
+            tex_coord: 0,
+            alpha_mode: AlphaMode::Opaque,
+            double_sided: false,
+        }
+    }
+}
+
+//a Texture for every texture in the document, in document order. images are what gltf::import returned
+pub fn load_gltf_textures(
+    document: &gltf::Document,
+    images: &[gltf::image::Data],
+) -> Result<Vec<Arc<Texture>>, TextureError> {
+    document
+        .textures()
+        .map(|texture| {
+            let mut result = Texture::from_gltf(&images[texture.source().index()])?;
+            result.sampler = Sampler::from_gltf(&texture.sampler());
+            Ok(Arc::new(result))
+        })
+        .collect()
+}
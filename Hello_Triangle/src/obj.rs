@@ -0,0 +1,283 @@
+use crate::blend::AlphaMode;
+use crate::error::{Error, Result};
+use crate::geometry::{face_normal, Mesh, Vertex};
+use crate::material::Material;
+use crate::scene::Scene;
+use crate::texture::Texture;
+use glam::{UVec3, Vec2, Vec3};
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+//a Wavefront OBJ file. Every usemtl starts a mesh section, their material indices point into materials
+pub struct Obj {
+    pub mesh: Mesh,
+    pub materials: Vec<ObjMaterial>,
+}
+
+//the parts of an MTL material DefaultShader can use
+#[derive(Debug, Clone, PartialEq)]
+pub struct ObjMaterial {
+    pub name: String,
+    pub diffuse: Vec3,                    //Kd
+    pub alpha: f32,                       //d, or 1 - Tr
+    pub diffuse_texture: Option<PathBuf>, //map_Kd, relative paths are resolved against the MTL file
+}
+
+impl Default for ObjMaterial {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            diffuse: Vec3::ONE,
+            alpha: 1.0,
+            diffuse_texture: None,
+        }
+    }
+}
+
+impl ObjMaterial {
+    //loads the texture, or takes it from textures if another material already did
+    pub fn to_material(&self, textures: &mut HashMap<PathBuf, Arc<Texture>>) -> Result<Material> {
+        let base_color_texture = match &self.diffuse_texture {
+            Some(path) => match textures.get(path) {
+                Some(texture) => Some(texture.clone()),
+                None => {
+                    let texture = Arc::new(Texture::load(path)?);
+                    textures.insert(path.clone(), texture.clone());
+                    Some(texture)
+                }
+            },
+            None => None,
+        };
+        Ok(Material {
+            base_color_texture,
+            base_color_factor: self.diffuse.extend(self.alpha),
+            alpha_mode: if self.alpha < 1.0 { AlphaMode::Blend } else { AlphaMode::Opaque },
+            ..Default::default()
+        })
+    }
+}
+
+impl Obj {
+    //one node drawing every section with its material, faces without usemtl get the default one
+    pub fn into_scene(self) -> Result<Scene> {
+        let mut textures = HashMap::new();
+        let materials = self
+            .materials
+            .iter()
+            .map(|material| material.to_material(&mut textures))
+            .collect::<Result<Vec<_>>>()?;
+        let primitives = self
+            .mesh
+            .split_sections()
+            .into_iter()
+            .zip(self.mesh.sections())
+            .map(|(mut mesh, section)| {
+                let material = section.material.map_or_else(Material::default, |id| materials[id].clone());
+                mesh.set_alpha_mode(material.alpha_mode);
+                (mesh, material)
+            })
+            .collect();
+        Ok(Scene::from_primitives(primitives))
+    }
+}
+
+//how a face corner gets its normal. Corners without one share the normal of their face,
+//so they can only be merged with corners of the same face
+#[derive(Copy, Clone, PartialEq, Eq, Hash)]
+enum CornerNormal {
+    Indexed(usize),
+    Flat(usize),
+}
+
+pub fn load_obj(path: &Path) -> Result<Obj> {
+    let source = fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+
+    let mut positions: Vec<Vec3> = Vec::new();
+    let mut colors: Vec<Vec3> = Vec::new();
+    let mut normals: Vec<Vec3> = Vec::new();
+    let mut uvs: Vec<Vec2> = Vec::new();
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+
+    let mut mesh = Mesh::new();
+    //the section being built, flushed into mesh on every usemtl and at the end
+    let mut material: Option<usize> = None;
+    let mut triangles: Vec<UVec3> = Vec::new();
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut corners: HashMap<(usize, Option<usize>, CornerNormal), u32> = HashMap::new();
+    let mut faces = 0;
+
+    for (line_id, line) in source.lines().enumerate() {
+        let invalid = |what: &str| Error::Invalid(format!("{} line {}: {}", path.display(), line_id + 1, what));
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+        match keyword {
+            "v" => {
+                let values = parse_floats(&args).ok_or_else(|| invalid("bad vertex"))?;
+                if values.len() < 3 {
+                    return Err(invalid("vertex needs x y z"));
+                }
+                positions.push(Vec3::from_slice(&values));
+                //some exporters put a vertex color after the position
+                colors.push(if values.len() >= 6 { Vec3::from_slice(&values[3..]) } else { Vec3::ONE });
+            }
+            "vn" => {
+                let values = parse_floats(&args).filter(|v| v.len() >= 3).ok_or_else(|| invalid("bad normal"))?;
+                normals.push(Vec3::from_slice(&values).normalize_or_zero());
+            }
+            "vt" => {
+                let values = parse_floats(&args).filter(|v| !v.is_empty()).ok_or_else(|| invalid("bad uv"))?;
+                //v goes up in OBJ, textures are stored top row first
+                uvs.push(Vec2::new(values[0], 1.0 - values.get(1).unwrap_or(&0.0)));
+            }
+            "f" => {
+                if args.len() < 3 {
+                    return Err(invalid("face needs 3 corners"));
+                }
+                let face: Vec<(usize, Option<usize>, Option<usize>)> = args
+                    .iter()
+                    .map(|corner| parse_corner(corner, positions.len(), uvs.len(), normals.len()))
+                    .collect::<Option<_>>()
+                    .ok_or_else(|| invalid("bad face index"))?;
+                let flat = face_normal(positions[face[0].0], positions[face[1].0], positions[face[2].0]);
+
+                let mut ids = Vec::with_capacity(face.len());
+                for (v, vt, vn) in face {
+                    let normal = vn.map_or(CornerNormal::Flat(faces), CornerNormal::Indexed);
+                    let id = *corners.entry((v, vt, normal)).or_insert_with(|| {
+                        vertices.push(Vertex::Construct(
+                            positions[v].extend(1.0),
+                            vn.map_or(flat, |vn| normals[vn]),
+                            colors[v],
+                            vt.map_or(Vec2::ZERO, |vt| uvs[vt]),
+                        ));
+                        vertices.len() as u32 - 1
+                    });
+                    ids.push(id);
+                }
+                //fan, fine for the convex polygons exporters write
+                triangles.extend((1..ids.len() - 1).map(|i| UVec3::new(ids[0], ids[i], ids[i + 1])));
+                faces += 1;
+            }
+            //an unknown name is a typo or a missing mtllib, drawing it with the default material would hide that
+            "usemtl" => {
+                flush_section(&mut mesh, &mut triangles, &mut vertices, &mut corners, material);
+                let name = args.join(" ");
+                let id = materials.iter().position(|material| material.name == name);
+                material = Some(id.ok_or_else(|| invalid(&format!("no material named \"{}\"", name)))?);
+            }
+            //file names with spaces turn up more often than several libraries on one line
+            "mtllib" => {
+                materials.extend(load_mtl(&dir.join(args.join(" ")))?);
+            }
+            //groups, objects and smoothing groups don't change how anything is drawn
+            _ => {}
+        }
+    }
+    flush_section(&mut mesh, &mut triangles, &mut vertices, &mut corners, material);
+
+    Ok(Obj { mesh, materials })
+}
+
+//adds the faces since the last usemtl as a section, if there were any
+fn flush_section(
+    mesh: &mut Mesh,
+    triangles: &mut Vec<UVec3>,
+    vertices: &mut Vec<Vertex>,
+    corners: &mut HashMap<(usize, Option<usize>, CornerNormal), u32>,
+    material: Option<usize>,
+) {
+    if !triangles.is_empty() {
+        mesh.add_material_section_from_vertices(triangles, vertices, material);
+    }
+    triangles.clear();
+    vertices.clear();
+    corners.clear();
+}
+
+pub fn load_mtl(path: &Path) -> Result<Vec<ObjMaterial>> {
+    let source = fs::read_to_string(path)?;
+    let dir = path.parent().unwrap_or(Path::new(""));
+    let mut materials: Vec<ObjMaterial> = Vec::new();
+
+    for (line_id, line) in source.lines().enumerate() {
+        let invalid = |what: &str| Error::Invalid(format!("{} line {}: {}", path.display(), line_id + 1, what));
+        let mut tokens = line.split_whitespace();
+        let Some(keyword) = tokens.next() else {
+            continue;
+        };
+        let args: Vec<&str> = tokens.collect();
+        if keyword == "newmtl" {
+            materials.push(ObjMaterial {
+                name: args.join(" "),
+                ..Default::default()
+            });
+            continue;
+        }
+        let Some(material) = materials.last_mut() else {
+            continue;
+        };
+        match keyword {
+            "Kd" => {
+                let values = parse_floats(&args).filter(|v| v.len() >= 3).ok_or_else(|| invalid("bad Kd"))?;
+                material.diffuse = Vec3::from_slice(&values);
+            }
+            "d" => material.alpha = parse_floats(&args).and_then(|v| v.first().copied()).ok_or_else(|| invalid("bad d"))?,
+            "Tr" => {
+                let transparency = parse_floats(&args).and_then(|v| v.first().copied()).ok_or_else(|| invalid("bad Tr"))?;
+                material.alpha = 1.0 - transparency;
+            }
+            "map_Kd" => {
+                let file = texture_file(&args).ok_or_else(|| invalid("map_Kd without a file"))?;
+                material.diffuse_texture = Some(dir.join(file));
+            }
+            _ => {}
+        }
+    }
+    Ok(materials)
+}
+
+//the file name of a map_ statement. Options like -s 1 1 1 come first and get skipped, the rest is
+//joined back together like mtllib does, file names with spaces are more common than ones starting with -
+fn texture_file(args: &[&str]) -> Option<String> {
+    let mut rest = args;
+    while let Some(option) = rest.first().filter(|arg| arg.starts_with('-')) {
+        //-o, -s and -t take 1 to 3 numbers, -mm always 2, everything else 1 value
+        let values = match *option {
+            "-o" | "-s" | "-t" => rest[1..].iter().take(3).take_while(|arg| arg.parse::<f32>().is_ok()).count(),
+            "-mm" => 2,
+            _ => 1,
+        };
+        rest = rest.get(1 + values..)?;
+    }
+    (!rest.is_empty()).then(|| rest.join(" "))
+}
+
+fn parse_floats(args: &[&str]) -> Option<Vec<f32>> {
+    args.iter().map(|arg| arg.parse().ok()).collect()
+}
+
+//v, v/vt, v//vn or v/vt/vn, 1 based. Negative indices count back from the last element read so far
+fn parse_corner(corner: &str, positions: usize, uvs: usize, normals: usize) -> Option<(usize, Option<usize>, Option<usize>)> {
+    let index = |value: &str, count: usize| -> Option<usize> {
+        let index: i64 = value.parse().ok()?;
+        let index = if index < 0 { count as i64 + index } else { index - 1 };
+        (0..count as i64).contains(&index).then_some(index as usize)
+    };
+    let mut parts = corner.split('/');
+    let v = index(parts.next()?, positions)?;
+    let vt = match parts.next() {
+        Some("") | None => None,
+        Some(vt) => Some(index(vt, uvs)?),
+    };
+    let vn = match parts.next() {
+        Some("") | None => None,
+        Some(vn) => Some(index(vn, normals)?),
+    };
+    Some((v, vt, vn))
+}
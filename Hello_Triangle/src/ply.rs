@@ -0,0 +1,236 @@
+use crate::error::{Error, Result};
+use crate::geometry::{Mesh, Vertex};
+use glam::{UVec3, Vec2, Vec3};
+use std::fs;
+use std::path::Path;
+
+//scalar types a property can have
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Scalar {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl Scalar {
+    fn parse(name: &str) -> Option<Self> {
+        Some(match name {
+            "char" | "int8" => Scalar::I8,
+            "uchar" | "uint8" => Scalar::U8,
+            "short" | "int16" => Scalar::I16,
+            "ushort" | "uint16" => Scalar::U16,
+            "int" | "int32" => Scalar::I32,
+            "uint" | "uint32" => Scalar::U32,
+            "float" | "float32" => Scalar::F32,
+            "double" | "float64" => Scalar::F64,
+            _ => return None,
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Scalar::I8 | Scalar::U8 => 1,
+            Scalar::I16 | Scalar::U16 => 2,
+            Scalar::I32 | Scalar::U32 | Scalar::F32 => 4,
+            Scalar::F64 => 8,
+        }
+    }
+
+    //colors are 0..255 in integer properties and 0..1 in float ones
+    fn max_color(self) -> f32 {
+        match self {
+            Scalar::F32 | Scalar::F64 => 1.0,
+            _ => 255.0,
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Property {
+    name: String,
+    scalar: Scalar,
+    list: Option<Scalar>, //type of the item count in front of a list property
+}
+
+#[derive(Debug)]
+struct Element {
+    name: String,
+    count: usize,
+    properties: Vec<Property>,
+}
+
+enum Body<'a> {
+    Ascii(std::str::SplitWhitespace<'a>),
+    Binary { bytes: &'a [u8], big_endian: bool },
+}
+
+impl Body<'_> {
+    fn value(&mut self, scalar: Scalar) -> Option<f64> {
+        match self {
+            Body::Ascii(tokens) => tokens.next()?.parse().ok(),
+            Body::Binary { bytes, big_endian } => {
+                let size = scalar.size();
+                if bytes.len() < size {
+                    return None;
+                }
+                let mut raw = [0u8; 8];
+                raw[..size].copy_from_slice(&bytes[..size]);
+                if *big_endian {
+                    raw[..size].reverse();
+                }
+                *bytes = &bytes[size..];
+                Some(match scalar {
+                    Scalar::I8 => raw[0] as i8 as f64,
+                    Scalar::U8 => raw[0] as f64,
+                    Scalar::I16 => i16::from_le_bytes([raw[0], raw[1]]) as f64,
+                    Scalar::U16 => u16::from_le_bytes([raw[0], raw[1]]) as f64,
+                    Scalar::I32 => i32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::U32 => u32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::F32 => f32::from_le_bytes([raw[0], raw[1], raw[2], raw[3]]) as f64,
+                    Scalar::F64 => f64::from_le_bytes(raw),
+                })
+            }
+        }
+    }
+}
+
+//ASCII or binary PLY, both byte orders. Reads positions, normals, colors and uvs of the vertex element and
+//fans the polygons of the face element, everything else is skipped. Without normals in the file
+//every vertex gets the average of the faces around it, which suits scans
+pub fn load_ply(path: &Path) -> Result<Mesh> {
+    let invalid = |what: &str| Error::Invalid(format!("{}: {}", path.display(), what));
+    let bytes = fs::read(path)?;
+
+    //header lines up to the one that is just end_header, a comment can mention it too.
+    //the body starts right after that line, binary ones can hold any byte
+    let mut header: Vec<&str> = Vec::new();
+    let mut body_start = None;
+    let mut line_start = 0;
+    while line_start < bytes.len() {
+        let line_end = bytes[line_start..]
+            .iter()
+            .position(|&byte| byte == b'\n')
+            .map_or(bytes.len(), |end| line_start + end);
+        let line = std::str::from_utf8(&bytes[line_start..line_end]).map_err(|_| invalid("header isn't text"))?;
+        line_start = line_end + 1;
+        if line.trim() == "end_header" {
+            body_start = Some(line_start.min(bytes.len()));
+            break;
+        }
+        header.push(line);
+    }
+    let body_start = body_start.ok_or_else(|| invalid("no end_header"))?;
+
+    let mut lines = header.into_iter();
+    if lines.next().map(str::trim) != Some("ply") {
+        return Err(invalid("not a PLY file"));
+    }
+    let mut format = None;
+    let mut elements: Vec<Element> = Vec::new();
+    for line in lines {
+        let tokens: Vec<&str> = line.split_whitespace().collect();
+        match tokens.as_slice() {
+            ["format", format_name, _] => format = Some(format_name.to_string()),
+            ["element", name, count] => elements.push(Element {
+                name: name.to_string(),
+                count: count.parse().map_err(|_| invalid("bad element count"))?,
+                properties: Vec::new(),
+            }),
+            ["property", "list", count, item, name] => {
+                let element = elements.last_mut().ok_or_else(|| invalid("property outside an element"))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    scalar: Scalar::parse(item).ok_or_else(|| invalid("bad property type"))?,
+                    list: Some(Scalar::parse(count).ok_or_else(|| invalid("bad property type"))?),
+                });
+            }
+            ["property", scalar, name] => {
+                let element = elements.last_mut().ok_or_else(|| invalid("property outside an element"))?;
+                element.properties.push(Property {
+                    name: name.to_string(),
+                    scalar: Scalar::parse(scalar).ok_or_else(|| invalid("bad property type"))?,
+                    list: None,
+                });
+            }
+            _ => {}
+        }
+    }
+
+    let body = &bytes[body_start..];
+    let mut body = match format.as_deref() {
+        Some("ascii") => Body::Ascii(std::str::from_utf8(body).map_err(|_| invalid("body isn't text"))?.split_whitespace()),
+        Some("binary_little_endian") => Body::Binary { bytes: body, big_endian: false },
+        Some("binary_big_endian") => Body::Binary { bytes: body, big_endian: true },
+        _ => return Err(Error::Unsupported(format!("PLY format {:?}", format.unwrap_or_default()))),
+    };
+
+    let mut vertices: Vec<Vertex> = Vec::new();
+    let mut has_normals = false;
+    let mut triangles: Vec<UVec3> = Vec::new();
+    for element in &elements {
+        for _ in 0..element.count {
+            let mut vertex = Vertex::Construct(glam::Vec4::W, Vec3::ZERO, Vec3::ONE, Vec2::ZERO);
+            for property in &element.properties {
+                match property.list {
+                    None => {
+                        let value = body.value(property.scalar).ok_or_else(|| invalid("file ends early"))? as f32;
+                        let color = value / property.scalar.max_color();
+                        match property.name.as_str() {
+                            "x" => vertex.position.x = value,
+                            "y" => vertex.position.y = value,
+                            "z" => vertex.position.z = value,
+                            "nx" => vertex.normal.x = value,
+                            "ny" => vertex.normal.y = value,
+                            "nz" => vertex.normal.z = value,
+                            "red" | "r" => vertex.color.x = color,
+                            "green" | "g" => vertex.color.y = color,
+                            "blue" | "b" => vertex.color.z = color,
+                            "u" | "s" | "texture_u" => vertex.uv.x = value,
+                            //v goes up like in OBJ, textures are stored top row first
+                            "v" | "t" | "texture_v" => vertex.uv.y = 1.0 - value,
+                            _ => {}
+                        }
+                        has_normals |= element.name == "vertex" && property.name == "nx";
+                    }
+                    Some(count_type) => {
+                        let count = body.value(count_type).ok_or_else(|| invalid("file ends early"))? as usize;
+                        let items = (0..count)
+                            .map(|_| body.value(property.scalar).map(|item| item as u32))
+                            .collect::<Option<Vec<u32>>>()
+                            .ok_or_else(|| invalid("file ends early"))?;
+                        let is_indices = matches!(property.name.as_str(), "vertex_indices" | "vertex_index");
+                        if element.name == "face" && is_indices && count >= 3 {
+                            triangles.extend((1..count - 1).map(|i| UVec3::new(items[0], items[i], items[i + 1])));
+                        }
+                    }
+                }
+            }
+            if element.name == "vertex" {
+                vertices.push(vertex);
+            }
+        }
+    }
+
+    if triangles.iter().flat_map(|tri| tri.to_array()).any(|id| id as usize >= vertices.len()) {
+        return Err(invalid("face index past the last vertex"));
+    }
+    if !has_normals {
+        //cross products are twice the triangle's area long, so bigger faces weigh more
+        for tri in &triangles {
+            let [a, b, c] = tri.to_array().map(|id| vertices[id as usize].position.truncate());
+            let normal = (b - a).cross(c - a);
+            for id in tri.to_array() {
+                vertices[id as usize].normal += normal;
+            }
+        }
+        for vertex in &mut vertices {
+            vertex.normal = vertex.normal.normalize_or_zero();
+        }
+    }
+    Ok(Mesh::from_vertices(&triangles, &vertices))
+}
@@ -0,0 +1,272 @@
+use crate::geometry::BoundingBox2D;
+use crate::tile::TargetSlice;
+use crate::utils::{from_argb8, to_argb8};
+use glam::{Vec2, Vec4};
+
+//rectangle of whole pixels, x and y are the top left corner
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn origin(&self) -> Vec2 {
+        glam::vec2(self.x as f32, self.y as f32)
+    }
+
+    pub fn size(&self) -> Vec2 {
+        glam::vec2(self.width as f32, self.height as f32)
+    }
+
+    pub fn intersect(&self, other: &Rect) -> Rect {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = (self.x + self.width).min(other.x + other.width);
+        let bot = (self.y + self.height).min(other.y + other.height);
+        Rect::new(x, y, right.saturating_sub(x), bot.saturating_sub(y))
+    }
+
+    //inclusive pixel bounds, None if the rect is empty
+    pub fn bounds(&self) -> Option<BoundingBox2D> {
+        if self.width == 0 || self.height == 0 {
+            return None;
+        }
+        Some(BoundingBox2D {
+            left: self.x as f32,
+            right: (self.x + self.width - 1) as f32,
+            top: self.y as f32,
+            bot: (self.y + self.height - 1) as f32,
+        })
+    }
+}
+
+//samples per pixel, fixed when the render target is created. Sample positions are the usual
+//D3D/Vulkan standard patterns, see edge::sample_offsets
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SampleCount {
+    X1 = 1,
+    X2 = 2,
+    X4 = 4,
+    X8 = 8,
+}
+
+impl SampleCount {
+    pub fn count(self) -> usize {
+        self as usize
+    }
+}
+
+//extra per pixel storage next to color and depth, e.g. normals or object ids for a deferred pass.
+//storage only, the rasterizer and FragmentShader never write to it. Fill it yourself, e.g. from depth after a pass
+pub struct Attachment {
+    pub data: Vec<Vec4>,
+    pub clear_value: Vec4,
+}
+
+//color and depth buffers of one size plus the part of them that gets drawn to.
+//ndc gets mapped onto the viewport, the scissor only cuts pixels away and doesn't move anything.
+//with multisampling the rasterizer draws into per sample buffers and color/depth only hold
+//something after resolve()
+pub struct RenderTarget {
+    width: usize,
+    height: usize,
+    samples: SampleCount,
+    pub color: Vec<u32>,
+    pub depth: Vec<f32>,
+    pub stencil: Vec<u8>, //see RasterState::stencil
+    //all samples of a pixel next to each other, empty without multisampling
+    sample_color: Vec<u32>,
+    sample_depth: Vec<f32>,
+    sample_stencil: Vec<u8>,
+    pub attachments: Vec<Attachment>,
+    pub viewport: Rect,
+    pub scissor: Option<Rect>,
+    //closer pixels have greater depth, see Camera::reverse_z. far_depth and resolve follow it
+    reverse_z: bool,
+}
+
+impl RenderTarget {
+    //starts out cleared to black and infinitely far depth, viewport covering the whole target
+    pub fn new(width: usize, height: usize) -> Self {
+        Self::with_samples(width, height, SampleCount::X1)
+    }
+
+    pub fn with_samples(width: usize, height: usize, samples: SampleCount) -> Self {
+        let sample_len = if samples == SampleCount::X1 { 0 } else { width * height * samples.count() };
+        Self {
+            width,
+            height,
+            samples,
+            color: vec![0; width * height],
+            depth: vec![f32::INFINITY; width * height],
+            stencil: vec![0; width * height],
+            sample_color: vec![0; sample_len],
+            sample_depth: vec![f32::INFINITY; sample_len],
+            sample_stencil: vec![0; sample_len],
+            attachments: Vec::new(),
+            viewport: Rect::new(0, 0, width, height),
+            scissor: None,
+            reverse_z: false,
+        }
+    }
+
+    //for Camera::reverse_z, depth is cleared to the far_depth that goes with it
+    pub fn with_reverse_z(mut self, reverse_z: bool) -> Self {
+        self.reverse_z = reverse_z;
+        let far = self.far_depth();
+        self.clear_depth(far);
+        self
+    }
+
+    pub fn reverse_z(&self) -> bool {
+        self.reverse_z
+    }
+
+    pub fn samples(&self) -> SampleCount {
+        self.samples
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn size(&self) -> Vec2 {
+        glam::vec2(self.width as f32, self.height as f32)
+    }
+
+    pub fn full_rect(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    //returns the index of the new attachment in attachments
+    pub fn add_attachment(&mut self, clear_value: Vec4) -> usize {
+        self.attachments.push(Attachment {
+            data: vec![clear_value; self.width * self.height],
+            clear_value,
+        });
+        self.attachments.len() - 1
+    }
+
+    //depth to clear to so that everything drawn is in front of it
+    pub fn far_depth(&self) -> f32 {
+        if self.reverse_z {
+            f32::NEG_INFINITY
+        } else {
+            f32::INFINITY
+        }
+    }
+
+    //clears every attachment, stencil goes to 0 and extra ones go back to their own clear value
+    pub fn clear(&mut self, color: u32, depth: f32) {
+        self.clear_color(color);
+        self.clear_depth(depth);
+        self.clear_stencil(0);
+        for attachment in &mut self.attachments {
+            attachment.data.fill(attachment.clear_value);
+        }
+    }
+
+    pub fn clear_color(&mut self, color: u32) {
+        self.color.fill(color);
+        self.sample_color.fill(color);
+    }
+
+    pub fn clear_depth(&mut self, depth: f32) {
+        self.depth.fill(depth);
+        self.sample_depth.fill(depth);
+    }
+
+    pub fn clear_stencil(&mut self, stencil: u8) {
+        self.stencil.fill(stencil);
+        self.sample_stencil.fill(stencil);
+    }
+
+    //averages the samples of every pixel into color, depth keeps the closest sample and stencil the first one.
+    //does nothing without multisampling, the rasterizer writes color and depth directly then
+    pub fn resolve(&mut self) {
+        let samples = self.samples.count();
+        if samples == 1 {
+            return;
+        }
+
+        let closest = if self.reverse_z { f32::max } else { f32::min };
+        let far = self.far_depth();
+        let pixels = self.sample_color.chunks(samples).zip(self.sample_depth.chunks(samples));
+        for ((color, depth), (sample_color, sample_depth)) in self.color.iter_mut().zip(&mut self.depth).zip(pixels) {
+            let mut sum = [0u32; 4];
+            for &argb in sample_color {
+                let (a, r, g, b) = from_argb8(argb);
+                for (channel, value) in sum.iter_mut().zip([a, r, g, b]) {
+                    *channel += value as u32;
+                }
+            }
+            //rounded to nearest
+            let [a, r, g, b] = sum.map(|channel| ((channel + samples as u32 / 2) / samples as u32) as u8);
+            *color = to_argb8(a, r, g, b);
+            *depth = sample_depth.iter().copied().fold(far, closest);
+        }
+        for (stencil, sample_stencil) in self.stencil.iter_mut().zip(self.sample_stencil.chunks(samples)) {
+            *stencil = sample_stencil[0];
+        }
+    }
+
+    //contents are thrown away and depth is cleared to far_depth. A viewport covering the whole old target
+    //covers the whole new one, any other viewport is kept as it was. The scissor is kept, clamped to the new size
+    pub fn resize(&mut self, width: usize, height: usize) {
+        let viewport = if self.viewport == self.full_rect() { Rect::new(0, 0, width, height) } else { self.viewport };
+        let scissor = self.scissor.map(|scissor| scissor.intersect(&Rect::new(0, 0, width, height)));
+        *self = Self {
+            attachments: self
+                .attachments
+                .iter()
+                .map(|a| Attachment {
+                    data: vec![a.clear_value; width * height],
+                    clear_value: a.clear_value,
+                })
+                .collect(),
+            viewport,
+            scissor,
+            reverse_z: self.reverse_z,
+            ..Self::with_samples(width, height, self.samples)
+        };
+        let far = self.far_depth();
+        self.clear_depth(far);
+    }
+
+    //pixels that may be written, viewport and scissor clamped to the target. None if nothing is left
+    pub fn draw_bounds(&self) -> Option<BoundingBox2D> {
+        let rect = self.viewport.intersect(&self.full_rect());
+        match &self.scissor {
+            Some(scissor) => rect.intersect(scissor).bounds(),
+            None => rect.bounds(),
+        }
+    }
+
+    //the buffers the rasterizer draws into, the per sample ones with multisampling
+    pub fn slice(&mut self) -> TargetSlice<'_> {
+        let (width, samples, reverse_z) = (self.width, self.samples.count(), self.reverse_z);
+        let slice = match self.samples {
+            SampleCount::X1 => TargetSlice::new(&mut self.color, &mut self.depth, &mut self.stencil, 0, width, samples),
+            _ => TargetSlice::new(
+                &mut self.sample_color,
+                &mut self.sample_depth,
+                &mut self.sample_stencil,
+                0,
+                width,
+                samples,
+            ),
+        };
+        TargetSlice { reverse_z, ..slice }
+    }
+}
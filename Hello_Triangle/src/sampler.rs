@@ -0,0 +1,81 @@
+use gltf::texture::{MagFilter, MinFilter, WrappingMode};
+
+//how texels get picked, see Texture::sample
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Filter {
+    //closest texel of the full size texture, aliases when minified
+    #[default]
+    Nearest,
+    //blend of the 4 closest texels of the full size texture, glTF's LINEAR without mipmapping
+    Linear,
+    //blend of the 4 closest texels in the mip level closest to the pixel's footprint
+    Bilinear,
+    //bilinear in the two mip levels around the footprint, blended between them
+    Trilinear,
+}
+
+//what happens to coordinates outside 0..1, per axis
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum Wrap {
+    #[default]
+    Repeat,
+    MirroredRepeat, //every other repetition is flipped
+    ClampToEdge,    //the border texels stretch on forever
+}
+
+impl Wrap {
+    //texel index inside 0..size for any whole texel coordinate, negative ones included
+    pub fn texel(self, coord: i64, size: usize) -> usize {
+        let size = size as i64;
+        let texel = match self {
+            Wrap::Repeat => coord.rem_euclid(size),
+            Wrap::MirroredRepeat => {
+                let period = coord.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+            Wrap::ClampToEdge => coord.clamp(0, size - 1),
+        };
+        texel as usize
+    }
+
+    pub fn from_gltf(mode: WrappingMode) -> Self {
+        match mode {
+            WrappingMode::Repeat => Wrap::Repeat,
+            WrappingMode::MirroredRepeat => Wrap::MirroredRepeat,
+            WrappingMode::ClampToEdge => Wrap::ClampToEdge,
+        }
+    }
+}
+
+//sampling settings that belong to a texture rather than to its pixels. u wraps along the width, v along the height
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub struct Sampler {
+    pub filter: Filter,
+    pub wrap_u: Wrap,
+    pub wrap_v: Wrap,
+}
+
+impl Sampler {
+    //glTF wrapS and wrapT map straight to the wrap modes. Filtering comes from minFilter since minification
+    //is what mip levels are for, magFilter only matters when that is missing
+    pub fn from_gltf(sampler: &gltf::texture::Sampler) -> Self {
+        let filter = match (sampler.min_filter(), sampler.mag_filter()) {
+            (Some(MinFilter::NearestMipmapLinear | MinFilter::LinearMipmapLinear), _) => Filter::Trilinear,
+            (Some(MinFilter::LinearMipmapNearest), _) => Filter::Bilinear,
+            (Some(MinFilter::Linear), _) => Filter::Linear,
+            (Some(MinFilter::Nearest | MinFilter::NearestMipmapNearest), _) => Filter::Nearest,
+            (None, Some(MagFilter::Nearest)) => Filter::Nearest,
+            //left to the implementation by the spec
+            (None, _) => Filter::Trilinear,
+        };
+        Self {
+            filter,
+            wrap_u: Wrap::from_gltf(sampler.wrap_s()),
+            wrap_v: Wrap::from_gltf(sampler.wrap_t()),
+        }
+    }
+}
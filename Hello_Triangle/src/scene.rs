@@ -0,0 +1,131 @@
+use crate::error::{Error, Result};
+use crate::geometry::Mesh;
+use crate::material::{load_gltf_textures, Material};
+use crate::transform::Transform;
+use glam::Mat4;
+
+//one node of the hierarchy, children index into Scene::nodes
+#[derive(Clone)]
+pub struct Node {
+    pub name: Option<String>,
+    pub transform: Transform, //relative to the parent
+    pub world: Mat4,          //model matrix including every parent, see Scene::update_world
+    pub mesh: Option<usize>,  //into Scene::meshes
+    pub children: Vec<usize>,
+}
+
+//a glTF scene. Meshes can be shared by several nodes, each one is drawn once per node that uses it
+#[derive(Clone, Default)]
+pub struct Scene {
+    pub nodes: Vec<Node>,                   //every node in the document, same indices as in the file
+    pub roots: Vec<usize>,                  //top level nodes of the scene that gets displayed
+    pub meshes: Vec<Vec<(Mesh, Material)>>, //primitives of every glTF mesh
+}
+
+impl Scene {
+    //the document's default scene, or the first one if it doesn't say
+    pub fn from_gltf(
+        document: &gltf::Document,
+        buffers: &[gltf::buffer::Data],
+        images: &[gltf::image::Data],
+    ) -> Result<Self> {
+        let textures = load_gltf_textures(document, images)?;
+        let meshes = document
+            .meshes()
+            .map(|mesh| {
+                mesh.primitives()
+                    .map(|primitive| {
+                        let material = Material::from_gltf(&primitive.material(), &textures);
+                        Ok((Mesh::load_primitive_from_gltf(&primitive, buffers, &material)?, material))
+                    })
+                    .collect()
+            })
+            .collect::<Result<_>>()?;
+        let nodes: Vec<Node> = document
+            .nodes()
+            .map(|node| Node {
+                name: node.name().map(String::from),
+                transform: Transform::from_gltf(node.transform()),
+                world: Mat4::IDENTITY,
+                mesh: node.mesh().map(|mesh| mesh.index()),
+                children: node.children().map(|child| child.index()).collect(),
+            })
+            .collect();
+        check_hierarchy(&nodes)?;
+        let roots = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .map_or_else(Vec::new, |scene| scene.nodes().map(|node| node.index()).collect());
+
+        let mut scene = Self { nodes, roots, meshes };
+        scene.update_world();
+        Ok(scene)
+    }
+
+    //a single node drawing the given primitives, for formats without a hierarchy
+    pub fn from_primitives(primitives: Vec<(Mesh, Material)>) -> Self {
+        Self {
+            nodes: vec![Node {
+                name: None,
+                transform: Transform::IDENTITY,
+                world: Mat4::IDENTITY,
+                mesh: Some(0),
+                children: Vec::new(),
+            }],
+            roots: vec![0],
+            meshes: vec![primitives],
+        }
+    }
+
+    //recomputes every world matrix from the local transforms, call it after changing them
+    pub fn update_world(&mut self) {
+        let mut stack: Vec<(usize, Mat4)> = self.roots.iter().map(|&root| (root, Mat4::IDENTITY)).collect();
+        while let Some((id, parent)) = stack.pop() {
+            let node = &mut self.nodes[id];
+            node.world = parent * node.transform.local();
+            stack.extend(node.children.iter().map(|&child| (child, node.world)));
+        }
+    }
+
+    //every primitive of every node under the roots with the node's world matrix
+    pub fn draws(&self) -> Vec<(&Mesh, &Material, Mat4)> {
+        let mut draws = Vec::new();
+        let mut stack: Vec<usize> = self.roots.iter().rev().copied().collect();
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if let Some(mesh) = node.mesh {
+                draws.extend(self.meshes[mesh].iter().map(|(mesh, material)| (mesh, material, node.world)));
+            }
+            stack.extend(node.children.iter().rev());
+        }
+        draws
+    }
+}
+
+//update_world and draws walk down from the roots, a node with two parents or a loop of nodes would
+//make them draw things twice or never finish. glTF forbids both but the gltf crate doesn't check
+fn check_hierarchy(nodes: &[Node]) -> Result<()> {
+    let mut parents = vec![0; nodes.len()];
+    for (id, node) in nodes.iter().enumerate() {
+        for &child in &node.children {
+            parents[child] += 1;
+            if parents[child] > 1 {
+                return Err(Error::Invalid(format!("node {} has more than one parent", child)));
+            }
+            if child == id {
+                return Err(Error::Invalid(format!("node {} is its own child", id)));
+            }
+        }
+    }
+    //with one parent at most, every node not under a parentless one sits in a loop or below one
+    let mut reached = vec![false; nodes.len()];
+    let mut stack: Vec<usize> = (0..nodes.len()).filter(|&id| parents[id] == 0).collect();
+    while let Some(id) = stack.pop() {
+        reached[id] = true;
+        stack.extend(&nodes[id].children);
+    }
+    match reached.iter().position(|&reached| !reached) {
+        Some(id) => Err(Error::Invalid(format!("node {} is part of a loop in the node hierarchy", id))),
+        None => Ok(()),
+    }
+}
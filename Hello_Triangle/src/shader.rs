@@ -0,0 +1,110 @@
+use crate::geometry::{Varying, Vertex};
+use crate::material::Material;
+use crate::sampler::Filter;
+use crate::texture::Texture;
+use crate::utils::cofactor;
+use glam::{Mat4, Vec2, Vec3, Vec4, Vec4Swizzles};
+
+//runs once per mesh vertex. The output has to carry the clip space position,
+//everything else in it gets interpolated across the triangle and handed to the fragment shader
+pub trait VertexShader {
+    type Varying: Varying;
+
+    fn vertex(&self, vertex: &Vertex) -> Self::Varying;
+}
+
+//runs once per covered pixel that passed the depth test. attributes are perspective corrected,
+//return None to discard the pixel, it then doesn't write color or depth.
+//the result is rgba, what alpha does depends on the mesh's AlphaMode and the BlendState
+pub trait FragmentShader<V: Varying = Vertex> {
+    fn fragment(&self, attributes: &V) -> Option<Vec4>;
+
+    //return true to get fragment_with_derivatives called instead of fragment, e.g. to pick mip levels.
+    //the rasterizer then interpolates the attributes twice more per pixel
+    fn uses_derivatives(&self) -> bool {
+        false
+    }
+
+    fn fragment_with_derivatives(&self, attributes: &V, _derivatives: &Derivatives<V>) -> Option<Vec4> {
+        self.fragment(attributes)
+    }
+}
+
+//how much the attributes change one pixel to the right (ddx) and one pixel down (ddy), perspective included
+#[derive(Debug, Copy, Clone)]
+pub struct Derivatives<V> {
+    pub ddx: V,
+    pub ddy: V,
+}
+
+//a single directional light plus a flat ambient term, the way the rasterizer always shaded
+pub fn lambert(albedo: Vec3, normal: Vec3) -> Vec3 {
+    let n_dot_l = normal.dot(Vec3::ONE.normalize());
+    let ambient = glam::vec3(0.2, 0.2, 0.2);
+    albedo * n_dot_l + ambient
+}
+
+//transforms by the mvp and lights the vertex color, or the texture if there is one, times base_color.
+//the texture is filtered with its own sampler
+pub struct DefaultShader<'a> {
+    pub mvp: Mat4,
+    pub normal_mat: Mat4, //cofactor of the model matrix so non uniform scale doesn't skew normals
+    pub texture: Option<&'a Texture>,
+    pub base_color: Vec4, //rgba
+}
+
+impl<'a> DefaultShader<'a> {
+    pub fn new(model: &Mat4, mvp: &Mat4, texture: Option<&'a Texture>) -> Self {
+        Self {
+            mvp: *mvp,
+            normal_mat: cofactor(model),
+            texture,
+            base_color: Vec4::ONE,
+        }
+    }
+
+    pub fn from_material(model: &Mat4, mvp: &Mat4, material: &'a Material) -> Self {
+        Self {
+            base_color: material.base_color_factor,
+            ..Self::new(model, mvp, material.base_color_texture.as_deref())
+        }
+    }
+}
+
+impl VertexShader for DefaultShader<'_> {
+    type Varying = Vertex;
+
+    fn vertex(&self, vertex: &Vertex) -> Vertex {
+        let mut out = *vertex;
+        out.position = self.mvp * vertex.position.xyz().extend(1.0);
+        out.normal = (self.normal_mat * vertex.normal.extend(0.0)).xyz();
+        out
+    }
+}
+
+impl DefaultShader<'_> {
+    fn shade(&self, attributes: &Vertex, ddx: Vec2, ddy: Vec2) -> Vec4 {
+        let (albedo, alpha) = match self.texture {
+            Some(tex) => {
+                let argb = tex.sample(attributes.uv, ddx, ddy);
+                (argb.yzw(), argb.x)
+            }
+            None => (attributes.color, 1.0),
+        };
+        lambert(albedo * self.base_color.xyz(), attributes.normal).extend(alpha * self.base_color.w)
+    }
+}
+
+impl FragmentShader for DefaultShader<'_> {
+    fn fragment(&self, attributes: &Vertex) -> Option<Vec4> {
+        Some(self.shade(attributes, Vec2::ZERO, Vec2::ZERO))
+    }
+
+    fn uses_derivatives(&self) -> bool {
+        self.texture.is_some_and(|tex| matches!(tex.sampler.filter, Filter::Bilinear | Filter::Trilinear))
+    }
+
+    fn fragment_with_derivatives(&self, attributes: &Vertex, derivatives: &Derivatives<Vertex>) -> Option<Vec4> {
+        Some(self.shade(attributes, derivatives.ddx.uv, derivatives.ddy.uv))
+    }
+}
@@ -0,0 +1,80 @@
+use crate::depth::CompareFunction;
+
+//what happens to the stored stencil value, increment and decrement clamp at 255 and 0
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum StencilOp {
+    Keep,
+    Zero,
+    Replace, //with StencilState::reference
+    Increment,
+    Decrement,
+    Invert,
+}
+
+//stencil test and the ops run for its three outcomes. The test passes when
+//(reference & read_mask) compares against (stored & read_mask), only bits in write_mask get changed.
+//an outline for example draws the mesh with Always/Replace, then a scaled up copy with NotEqual
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct StencilState {
+    pub compare: CompareFunction,
+    pub reference: u8,
+    pub read_mask: u8,
+    pub write_mask: u8,
+    pub fail: StencilOp,       //stencil test failed, the pixel is dropped
+    pub depth_fail: StencilOp, //stencil test passed but the depth test didn't
+    pub pass: StencilOp,       //both passed and the fragment shader kept the pixel
+}
+
+impl StencilState {
+    //passes where the stored value equals reference, without changing it
+    pub fn equal(reference: u8) -> Self {
+        Self {
+            compare: CompareFunction::Equal,
+            reference,
+            ..Default::default()
+        }
+    }
+
+    //always passes and writes reference wherever the pixel gets drawn
+    pub fn write(reference: u8) -> Self {
+        Self {
+            compare: CompareFunction::Always,
+            reference,
+            pass: StencilOp::Replace,
+            ..Default::default()
+        }
+    }
+
+    #[inline]
+    pub fn test(&self, stored: u8) -> bool {
+        self.compare.test(self.reference & self.read_mask, stored & self.read_mask)
+    }
+
+    //stored value after op
+    #[inline]
+    pub fn apply(&self, op: StencilOp, stored: u8) -> u8 {
+        let value = match op {
+            StencilOp::Keep => return stored,
+            StencilOp::Zero => 0,
+            StencilOp::Replace => self.reference,
+            StencilOp::Increment => stored.saturating_add(1),
+            StencilOp::Decrement => stored.saturating_sub(1),
+            StencilOp::Invert => !stored,
+        };
+        (stored & !self.write_mask) | (value & self.write_mask)
+    }
+}
+
+impl Default for StencilState {
+    fn default() -> Self {
+        Self {
+            compare: CompareFunction::Always,
+            reference: 0,
+            read_mask: 0xFF,
+            write_mask: 0xFF,
+            fail: StencilOp::Keep,
+            depth_fail: StencilOp::Keep,
+            pass: StencilOp::Keep,
+        }
+    }
+}
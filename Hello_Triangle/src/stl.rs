@@ -0,0 +1,88 @@
+use crate::error::{Error, Result};
+use crate::geometry::{face_normal, Mesh, Vertex};
+use glam::{UVec3, Vec2, Vec3};
+use std::fs;
+use std::path::Path;
+
+//binary or ASCII STL. Facets don't share vertices and get flat normals worked out from their corners,
+//the normals in the file are often missing or wrong
+pub fn load_stl(path: &Path) -> Result<Mesh> {
+    let bytes = fs::read(path)?;
+    let corners = if let Some(facets) = binary_facets(&bytes) {
+        binary_corners(&bytes, facets)
+    } else if bytes.starts_with(b"solid") {
+        let source = std::str::from_utf8(&bytes).map_err(|_| invalid(path, "ASCII STL that isn't text"))?;
+        ascii_corners(source).map_err(|what| invalid(path, what))?
+    } else if bytes.len() >= 84 {
+        return Err(invalid(path, "binary STL ends early"));
+    } else {
+        return Err(invalid(path, "neither binary nor ASCII STL"));
+    };
+
+    let vertices: Vec<Vertex> = corners
+        .chunks_exact(3)
+        .flat_map(|facet| {
+            let normal = face_normal(facet[0], facet[1], facet[2]);
+            facet.iter().map(move |&corner| Vertex::Construct(corner.extend(1.0), normal, Vec3::ONE, Vec2::ZERO))
+        })
+        .collect();
+    let triangles: Vec<UVec3> = (0..vertices.len() as u32).step_by(3).map(|id| UVec3::new(id, id + 1, id + 2)).collect();
+    Ok(Mesh::from_vertices(&triangles, &vertices))
+}
+
+fn invalid(path: &Path, what: &str) -> Error {
+    Error::Invalid(format!("{}: {}", path.display(), what))
+}
+
+//facet count of a binary STL: an 80 byte header, the count and 50 bytes per facet. Binary files may start
+//with "solid" too, so the size is what tells them apart. Some exporters pad the end, anything after the facets is ignored.
+//ASCII files are text from byte 80 on, which reads as a count far too big for the file
+fn binary_facets(bytes: &[u8]) -> Option<usize> {
+    if bytes.len() < 84 {
+        return None;
+    }
+    let facets = u32::from_le_bytes([bytes[80], bytes[81], bytes[82], bytes[83]]) as usize;
+    (bytes.len() >= 84 + facets * 50).then_some(facets)
+}
+
+//a normal, three corners, then 2 bytes nobody agrees on the meaning of
+fn binary_corners(bytes: &[u8], facets: usize) -> Vec<Vec3> {
+    bytes[84..84 + facets * 50]
+        .chunks_exact(50)
+        .flat_map(|facet| {
+            (0..3).map(move |corner| {
+                let offset = 12 + corner * 12;
+                let float = |id: usize| {
+                    let at = offset + id * 4;
+                    f32::from_le_bytes([facet[at], facet[at + 1], facet[at + 2], facet[at + 3]])
+                };
+                Vec3::new(float(0), float(1), float(2))
+            })
+        })
+        .collect()
+}
+
+//every "vertex x y z" in order. Each facet has to hold exactly 3, a truncated file is an error rather than a smaller mesh
+fn ascii_corners(source: &str) -> std::result::Result<Vec<Vec3>, &'static str> {
+    let mut corners = Vec::new();
+    let mut facet_start = None;
+    let mut tokens = source.split_whitespace();
+    while let Some(token) = tokens.next() {
+        match token {
+            "facet" => facet_start = Some(corners.len()),
+            "endfacet" => match facet_start.take() {
+                Some(start) if corners.len() - start == 3 => {}
+                _ => return Err("facet without exactly 3 vertices"),
+            },
+            "vertex" => {
+                let mut coordinate = || tokens.next().and_then(|token| token.parse::<f32>().ok()).ok_or("bad vertex");
+                corners.push(Vec3::new(coordinate()?, coordinate()?, coordinate()?));
+            }
+            _ => {}
+        }
+    }
+    if facet_start.is_some() || !corners.len().is_multiple_of(3) {
+        return Err("file ends inside a facet");
+    }
+    Ok(corners)
+}
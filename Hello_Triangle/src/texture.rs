@@ -1,62 +1,139 @@
+use crate::sampler::{Filter, Sampler};
 use crate::utils::*;
-use glam::Vec4;
+use glam::{Vec2, Vec4};
 use stb_image;
+use std::fmt;
+use std::fs;
+use std::io;
 use std::path::Path;
 
+#[derive(Debug)]
+pub enum TextureError {
+    Io(io::Error),
+    Decode(String),  //stb_image's reason
+    Channels(usize), //only 1 to 4 channels map to argb
+}
+
+impl fmt::Display for TextureError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TextureError::Io(e) => write!(f, "couldn't read texture: {}", e),
+            TextureError::Decode(reason) => write!(f, "couldn't decode texture: {}", reason),
+            TextureError::Channels(depth) => write!(f, "unsupported texture with {} channels", depth),
+        }
+    }
+}
+
+impl std::error::Error for TextureError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            TextureError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone)]
 pub struct Texture {
     pub width: usize,
     pub height: usize,
     pub data: Vec<u32>,
     pub depth: usize,
+    pub sampler: Sampler,
+    //every level below the full size one, each half as big as the one before down to 1x1
+    mips: Vec<MipLevel>,
+}
+
+#[derive(Clone)]
+struct MipLevel {
+    width: usize,
+    height: usize,
+    data: Vec<u32>,
 }
 
 impl Texture {
-    pub fn Load(path: &Path) -> Self {
-        let decoded_image = stb_image::image::load(path);
-        if let stb_image::image::LoadResult::ImageU8(image) = decoded_image {
-            //no PNGs
-            let data;
-
-            if image.depth == 4 {
-                data = (0..image.data.len() / 4)
-                    .map(|id| {
-                        to_argb8(
-                            image.data[id * 4 + 3],
-                            image.data[id * 4],
-                            image.data[id * 4 + 1],
-                            image.data[id * 4 + 2],
-                        )
-                    })
-                    .collect();
-            } else {
-                data = (0..image.data.len() / 3)
-                    .map(|id| {
-                        to_argb8(
-                            255,
-                            image.data[id * 3],
-                            image.data[id * 3 + 1],
-                            image.data[id * 3 + 2],
-                        )
-                    })
-                    .collect();
-            }
+    //argb8 pixels, row by row. Builds the mip chain, data has to stay as it is afterwards
+    pub fn new(width: usize, height: usize, data: Vec<u32>, depth: usize) -> Self {
+        assert_eq!(data.len(), width * height);
+        let mut mips: Vec<MipLevel> = Vec::new();
+        let (mut w, mut h) = (width, height);
+        while w > 1 || h > 1 {
+            let src = mips.last().map_or(&data, |mip| &mip.data);
+            let mip = downsample(src, w, h);
+            (w, h) = (mip.width, mip.height);
+            mips.push(mip);
+        }
+
+        Self {
+            width,
+            height,
+            data,
+            depth,
+            sampler: Sampler::default(),
+            mips,
+        }
+    }
+
+    //png, jpg, tga, bmp, hdr... anything stb_image decodes
+    pub fn load(path: &Path) -> Result<Self, TextureError> {
+        let bytes = fs::read(path).map_err(TextureError::Io)?;
+        Self::from_memory(&bytes)
+    }
 
-            Self {
-                width: image.width,
-                height: image.height,
-                data,
-                depth: image.depth,
+    //an encoded image already in memory, like the ones embedded in glb files
+    pub fn from_memory(bytes: &[u8]) -> Result<Self, TextureError> {
+        match stb_image::image::load_from_memory(bytes) {
+            stb_image::image::LoadResult::ImageU8(image) => {
+                let data = texels(&image.data, image.depth, |channel| channel)?;
+                Ok(Self::new(image.width, image.height, data, image.depth))
             }
-        } else {
-            panic!("Unsupported texture type");
+            //hdr images are linear floats, everything above 1 saturates
+            stb_image::image::LoadResult::ImageF32(image) => {
+                let data = texels(&image.data, image.depth, |channel| (channel.clamp(0.0, 1.0) * 255.0) as u8)?;
+                Ok(Self::new(image.width, image.height, data, image.depth))
+            }
+            stb_image::image::LoadResult::Error(reason) => Err(TextureError::Decode(reason)),
         }
     }
 
+    //an image gltf::import already decoded. 16 bit channels keep their high byte, float ones saturate like hdr files
+    pub fn from_gltf(image: &gltf::image::Data) -> Result<Self, TextureError> {
+        use gltf::image::Format;
+        let (depth, channel_size) = match image.format {
+            Format::R8 => (1, 1),
+            Format::R8G8 => (2, 1),
+            Format::R8G8B8 => (3, 1),
+            Format::R8G8B8A8 => (4, 1),
+            Format::R16 => (1, 2),
+            Format::R16G16 => (2, 2),
+            Format::R16G16B16 => (3, 2),
+            Format::R16G16B16A16 => (4, 2),
+            Format::R32G32B32FLOAT => (3, 4),
+            Format::R32G32B32A32FLOAT => (4, 4),
+        };
+        let channels: Vec<u8> = match channel_size {
+            1 => image.pixels.clone(),
+            2 => image
+                .pixels
+                .chunks_exact(2)
+                .map(|c| (u16::from_ne_bytes([c[0], c[1]]) >> 8) as u8)
+                .collect(),
+            _ => image
+                .pixels
+                .chunks_exact(4)
+                .map(|c| (f32::from_ne_bytes([c[0], c[1], c[2], c[3]]).clamp(0.0, 1.0) * 255.0) as u8)
+                .collect(),
+        };
+        let data = texels(&channels, depth, |channel| channel)?;
+        Ok(Self::new(image.width as usize, image.height as usize, data, depth))
+    }
+
+    //texel under uv, outside 0..1 it follows the sampler's wrap modes
     pub fn uv_to_index(&self, u: f32, v: f32) -> usize {
         let (u, v) = (u * self.width as f32, v * self.height as f32);
         coords_to_index(
-            (u as usize) % self.width,
-            (v as usize) % self.height,
+            self.sampler.wrap_u.texel(u.floor() as i64, self.width),
+            self.sampler.wrap_v.texel(v.floor() as i64, self.height),
             self.width,
         )
     }
@@ -84,4 +161,113 @@ impl Texture {
             Vec4::new(1.0, 1.0, 0.0, 1.0)
         }
     }
+
+    //number of levels including the full size one
+    pub fn mip_levels(&self) -> usize {
+        self.mips.len() + 1
+    }
+
+    //size and pixels of a mip level, 0 is the full size texture
+    pub fn mip(&self, level: usize) -> (usize, usize, &[u32]) {
+        match level {
+            0 => (self.width, self.height, &self.data),
+            _ => {
+                let mip = &self.mips[level - 1];
+                (mip.width, mip.height, &mip.data)
+            }
+        }
+    }
+
+    //mip level whose texels are about one pixel big on screen, ddx and ddy are the uv change
+    //one pixel to the right and one pixel down. Not clamped to the levels there are
+    pub fn lod(&self, ddx: Vec2, ddy: Vec2) -> f32 {
+        let size = glam::vec2(self.width as f32, self.height as f32);
+        let footprint = (ddx * size).length().max((ddy * size).length());
+        footprint.max(f32::MIN_POSITIVE).log2()
+    }
+
+    //filtered color at uv following self.sampler, in the same (a, r, g, b) order as argb_at_uvf
+    pub fn sample(&self, uv: Vec2, ddx: Vec2, ddy: Vec2) -> Vec4 {
+        let max_level = (self.mip_levels() - 1) as f32;
+        match self.sampler.filter {
+            Filter::Nearest => self.argb_at_uvf(uv.x, uv.y),
+            Filter::Linear => self.bilinear(0, uv),
+            Filter::Bilinear => {
+                let level = self.lod(ddx, ddy).round().clamp(0.0, max_level);
+                self.bilinear(level as usize, uv)
+            }
+            Filter::Trilinear => {
+                let lod = self.lod(ddx, ddy).clamp(0.0, max_level);
+                let level = lod.floor() as usize;
+                let fine = self.bilinear(level, uv);
+                if lod.fract() == 0.0 {
+                    fine
+                } else {
+                    Lerp(fine, self.bilinear(level + 1, uv), lod.fract())
+                }
+            }
+        }
+    }
+
+    //the 4 texels around uv in one mip level, weighted by distance. Texels past the edges follow the wrap modes
+    pub fn bilinear(&self, level: usize, uv: Vec2) -> Vec4 {
+        let (width, height, data) = self.mip(level);
+        //texel centers sit at half coordinates
+        let x = uv.x * width as f32 - 0.5;
+        let y = uv.y * height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+
+        let texel = |dx: i64, dy: i64| {
+            let tx = self.sampler.wrap_u.texel(x0 as i64 + dx, width);
+            let ty = self.sampler.wrap_v.texel(y0 as i64 + dy, height);
+            argb8_to_vec4(data[coords_to_index(tx, ty, width)])
+        };
+        let top = Lerp(texel(0, 0), texel(1, 0), fx);
+        let bot = Lerp(texel(0, 1), texel(1, 1), fx);
+        Lerp(top, bot, fy)
+    }
+}
+
+fn argb8_to_vec4(argb: u32) -> Vec4 {
+    let (a, r, g, b) = from_argb8(argb);
+    glam::vec4(a as f32, r as f32, g as f32, b as f32) / 255.0
+}
+
+//packs decoded pixels of depth channels each into argb8. Gray goes to all three colors, alpha is opaque when missing
+fn texels<T: Copy>(pixels: &[T], depth: usize, to_u8: impl Fn(T) -> u8) -> Result<Vec<u32>, TextureError> {
+    let pack = |pixel: &[T]| {
+        let channel = |id: usize| to_u8(pixel[id]);
+        match depth {
+            1 => to_argb8(255, channel(0), channel(0), channel(0)),
+            2 => to_argb8(channel(1), channel(0), channel(0), channel(0)),
+            3 => to_argb8(255, channel(0), channel(1), channel(2)),
+            _ => to_argb8(channel(3), channel(0), channel(1), channel(2)),
+        }
+    };
+    match depth {
+        1..=4 => Ok(pixels.chunks_exact(depth).map(pack).collect()),
+        _ => Err(TextureError::Channels(depth)),
+    }
+}
+
+//half size level, every texel the rounded average of the 2x2 block above it. Odd sizes repeat the last row or column
+fn downsample(data: &[u32], width: usize, height: usize) -> MipLevel {
+    let (w, h) = ((width / 2).max(1), (height / 2).max(1));
+    let data = (0..w * h)
+        .map(|id| {
+            let (x, y) = index_to_coords(id, w);
+            let mut sum = [0u32; 4];
+            for (sx, sy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                let src = coords_to_index((2 * x + sx).min(width - 1), (2 * y + sy).min(height - 1), width);
+                let (a, r, g, b) = from_argb8(data[src]);
+                for (channel, value) in sum.iter_mut().zip([a, r, g, b]) {
+                    *channel += value as u32;
+                }
+            }
+            let [a, r, g, b] = sum.map(|channel| ((channel + 2) / 4) as u8);
+            to_argb8(a, r, g, b)
+        })
+        .collect();
+    MipLevel { width: w, height: h, data }
 }
@@ -0,0 +1,140 @@
+use crate::geometry::BoundingBox2D;
+use crate::render_target::RenderTarget;
+use crate::utils::coords_to_index;
+use glam::Vec2;
+use std::sync::Mutex;
+use std::thread;
+
+pub const TILE_SIZE: usize = 32;
+
+//horizontal band of the color and depth buffers, starting at row y.
+//rasterizing into a slice only touches the rows it owns, so slices can be handed to different threads.
+//with multisampling every pixel has `samples` entries next to each other
+pub struct TargetSlice<'a> {
+    pub y: usize,
+    pub width: usize,
+    pub samples: usize,
+    pub reverse_z: bool, //see RenderTarget::with_reverse_z, false from new
+    pub color: &'a mut [u32],
+    pub depth: &'a mut [f32],
+    pub stencil: &'a mut [u8],
+}
+
+impl<'a> TargetSlice<'a> {
+    pub fn new(
+        color: &'a mut [u32],
+        depth: &'a mut [f32],
+        stencil: &'a mut [u8],
+        y: usize,
+        width: usize,
+        samples: usize,
+    ) -> Self {
+        Self {
+            y,
+            width,
+            samples,
+            reverse_z: false,
+            color,
+            depth,
+            stencil,
+        }
+    }
+
+    //splits into bands of `rows` rows each, top to bottom. The last one is shorter if rows doesn't divide the height
+    pub fn into_bands(self, rows: usize) -> impl Iterator<Item = TargetSlice<'a>> {
+        let (y, width, samples, reverse_z) = (self.y, self.width, self.samples, self.reverse_z);
+        let len = rows * width * samples;
+        self.color
+            .chunks_mut(len)
+            .zip(self.depth.chunks_mut(len))
+            .zip(self.stencil.chunks_mut(len))
+            .enumerate()
+            .map(move |(i, ((color, depth), stencil))| TargetSlice {
+                reverse_z,
+                ..TargetSlice::new(color, depth, stencil, y + i * rows, width, samples)
+            })
+    }
+
+    //x and y are in screen space, not relative to the slice. Index of the pixel's first sample
+    pub fn index(&self, x: usize, y: usize) -> usize {
+        coords_to_index(x, y - self.y, self.width) * self.samples
+    }
+}
+
+//screen space triangles sorted into TILE_SIZE x TILE_SIZE tiles covering the whole render target.
+//each bin keeps triangle ids in submission order so depth ties resolve the same way as single threaded drawing
+pub struct TileBins {
+    pub tiles_x: usize,
+    pub tiles_y: usize,
+    pub bins: Vec<Vec<usize>>,
+    target_size: Vec2,
+}
+
+impl TileBins {
+    pub fn new(target_size: Vec2) -> Self {
+        //partial tiles at the right and bottom edges are fine, screen size doesn't need to divide by TILE_SIZE
+        let tiles_x = (target_size.x as usize).div_ceil(TILE_SIZE);
+        let tiles_y = (target_size.y as usize).div_ceil(TILE_SIZE);
+
+        Self {
+            tiles_x,
+            tiles_y,
+            bins: vec![Vec::new(); tiles_x * tiles_y],
+            target_size,
+        }
+    }
+
+    //bb has to be clamped to the viewport already, see triangle_screen_bounding_box
+    pub fn bin(&mut self, id: usize, bb: &BoundingBox2D) {
+        for ty in (bb.top as usize / TILE_SIZE)..=(bb.bot as usize / TILE_SIZE) {
+            for tx in (bb.left as usize / TILE_SIZE)..=(bb.right as usize / TILE_SIZE) {
+                self.bins[coords_to_index(tx, ty, self.tiles_x)].push(id);
+            }
+        }
+    }
+
+    pub fn tile_bounds(&self, tx: usize, ty: usize) -> BoundingBox2D {
+        let left = tx * TILE_SIZE;
+        let top = ty * TILE_SIZE;
+        let right = (left + TILE_SIZE).min(self.target_size.x as usize) - 1;
+        let bot = (top + TILE_SIZE).min(self.target_size.y as usize) - 1;
+
+        BoundingBox2D {
+            left: left as f32,
+            right: right as f32,
+            top: top as f32,
+            bot: bot as f32,
+        }
+    }
+}
+
+//shades all binned tiles on a pool of worker threads. A worker grabs a whole row of tiles at a time
+//since that is a contiguous part of every buffer, then draws every tile in it.
+//raster gets the tile bounds and the ids of the triangles binned into that tile
+pub fn raster_tiles<F>(bins: &TileBins, target: &mut RenderTarget, raster: F)
+where
+    F: Fn(&BoundingBox2D, &[usize], &mut TargetSlice) + Sync,
+{
+    let rows = Mutex::new(target.slice().into_bands(TILE_SIZE).enumerate());
+
+    let workers = thread::available_parallelism()
+        .map_or(1, |n| n.get())
+        .min(bins.tiles_y);
+
+    thread::scope(|scope| {
+        for _ in 0..workers {
+            scope.spawn(|| loop {
+                //don't hold the lock while drawing
+                let next = rows.lock().unwrap().next();
+                let Some((ty, mut target)) = next else {
+                    break;
+                };
+
+                for tx in 0..bins.tiles_x {
+                    let ids = &bins.bins[coords_to_index(tx, ty, bins.tiles_x)];
+                    raster(&bins.tile_bounds(tx, ty), ids, &mut target);
+                }
+            });
+        }
+    });
+}
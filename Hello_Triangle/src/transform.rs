@@ -52,6 +52,12 @@ impl Transform {
         }
     }
 
+    //a glTF node's TRS, matrices get decomposed. Shear can't be represented and is lost
+    pub fn from_gltf(transform: gltf::scene::Transform) -> Self {
+        let (translation, rotation, scale) = transform.decomposed();
+        Self::new(Vec3::from(translation), Quat::from_array(rotation), Vec3::from(scale))
+    }
+
     pub fn right(&self) -> Vec3 {
         self.rotation * Vec3::X
     }
@@ -1,26 +1,5 @@
-use std::thread::JoinHandle;
-
-use crate::geometry::Point;
-use glam::{Mat4, Vec2, Vec3};
-
-pub fn Barycentric_Coordinates(
-    point: Vec2,
-    v0: Vec2,
-    v1: Vec2,
-    v2: Vec2,
-    area: f32,
-) -> Option<Vec3> {
-    let a = 1.0 / area;
-    let m0 = edge_function(point, v1, v2) * a;
-    let m1 = edge_function(point, v2, v0) * a;
-    let m2 = 1.0 - m0 - m1;
-
-    if m0 >= 0.0 && m1 >= 0.0 && m2 >= 0.0 {
-        Some(glam::vec3(m0, m1, m2))
-    } else {
-        None
-    }
-}
+use crate::geometry::{BoundingBox2D, Point};
+use glam::{Mat4, Vec2};
 
 pub fn Lerp<T>(start: T, end: T, alpha: f32) -> T
 where
@@ -124,13 +103,47 @@ pub fn bresenham_function(vert0: Vec2, vert1: Vec2) -> Vec<Point>
         }
 
         if error2 < deltay {
-            error += deltay;
+            error += deltax;
             currY += stepY;
         }
     }
     coords
 }
 
+//Liang-Barsky, returns the part of the line from p0 to p1 inside bb as (t_start, t_end)
+pub fn clip_line(p0: Vec2, p1: Vec2, bb: &BoundingBox2D) -> Option<(f32, f32)> {
+    let delta = p1 - p0;
+    let mut t0: f32 = 0.0;
+    let mut t1: f32 = 1.0;
+
+    for (p, q) in [
+        (-delta.x, p0.x - bb.left),
+        (delta.x, bb.right - p0.x),
+        (-delta.y, p0.y - bb.top),
+        (delta.y, bb.bot - p0.y),
+    ] {
+        if p == 0.0 {
+            //parallel to this edge
+            if q < 0.0 {
+                return None;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+
+    if t0 > t1 {
+        None
+    } else {
+        Some((t0, t1))
+    }
+}
+
 //https://github.com/graphitemaster/normals_revisited
 pub fn minor(
     src: &[f32; 16],
@@ -169,23 +182,3 @@ pub fn cofactor(matrix: &Mat4) -> Mat4 {
     dst[15] = minor(&src, 0, 1, 2, 0, 1, 2);
     Mat4::from_cols_array(&dst)
 }
-
-// #[derive(Debug, Clone, Copy)]
-// pub struct Tile {
-//     joinHandle: JoinHandle<()>,
-//     coords: (usize, usize),
-// }
-
-// impl Tile {
-//     pub fn new(joinHandle: JoinHandle<()>, coords: (usize, usize)) -> Self {
-//         Self { joinHandle, coords }
-//     }
-//     //something else
-// }
-
-// impl Copy for Tile {
-//     fn Copy(rhs: Self) -> Self {
-//         rhs.joinHandle = self.joinHandle,
-//         rhs.coords = self.coords,
-//     }
-// }
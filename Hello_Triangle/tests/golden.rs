@@ -0,0 +1,181 @@
+//golden image tests. Every scene is rendered headlessly and compared against tests/golden/<name>.png.
+//on a mismatch the render and a diff image (red where pixels differ) land in target/tmp/golden.
+//run with GOLDEN_UPDATE=1 to rewrite the golden images after an intended change
+use glam::{Mat4, UVec3, Vec3};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+use Hello_Triangle::*;
+
+const WIDTH: usize = 64;
+const HEIGHT: usize = 64;
+//largest difference allowed per color channel, leaves room for float differences between platforms
+const TOLERANCE: u8 = 2;
+
+fn vertex(position: Vec3, normal: Vec3, color: Vec3, uv: glam::Vec2) -> Vertex {
+    Vertex::Construct(position.extend(1.0), normal, color, uv)
+}
+
+fn camera_at(translation: Vec3) -> Camera {
+    Camera {
+        transform: Transform::from_translation(translation),
+        frustum_near: 1.0,
+        frustum_far: 10.0,
+        ..Default::default()
+    }
+}
+
+//camera looking down -z from z = 3 at a 2x2 quad, uvs cover the whole texture
+fn quad(normal: Vec3) -> Mesh {
+    let vertices = [
+        vertex(glam::vec3(-1.0, -1.0, 0.0), normal, Vec3::ONE, glam::vec2(0.0, 1.0)),
+        vertex(glam::vec3(1.0, -1.0, 0.0), normal, Vec3::ONE, glam::vec2(1.0, 1.0)),
+        vertex(glam::vec3(1.0, 1.0, 0.0), normal, Vec3::ONE, glam::vec2(1.0, 0.0)),
+        vertex(glam::vec3(-1.0, 1.0, 0.0), normal, Vec3::ONE, glam::vec2(0.0, 0.0)),
+    ];
+    Mesh::from_vertices(&[UVec3::new(0, 1, 2), UVec3::new(0, 2, 3)], &vertices)
+}
+
+fn checker_texture(size: usize) -> Texture {
+    let data = (0..size * size)
+        .map(|id| {
+            let (x, y) = index_to_coords(id, size);
+            if (x + y) % 2 == 0 {
+                to_argb8(255, 230, 60, 30)
+            } else {
+                to_argb8(255, 30, 90, 220)
+            }
+        })
+        .collect();
+    Texture::new(size, size, data, 3)
+}
+
+fn render(mesh: &Mesh, texture: Option<&Texture>, model: &Mat4, camera: &Camera, render_type: RenderType) -> Vec<u32> {
+    let state = RasterState {
+        render_type,
+        ..Default::default()
+    };
+    let mut target = RenderTarget::new(WIDTH, HEIGHT);
+    render_headless(mesh, texture, model, camera, &mut target, &state);
+    target.color
+}
+
+fn read_png(path: &Path) -> Option<Vec<u32>> {
+    let decoder = png::Decoder::new(File::open(path).ok()?);
+    let mut reader = decoder.read_info().ok()?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).ok()?;
+    assert_eq!(
+        (info.width as usize, info.height as usize, info.color_type, info.bit_depth),
+        (WIDTH, HEIGHT, png::ColorType::Rgb, png::BitDepth::Eight),
+        "{} has the wrong size or format",
+        path.display()
+    );
+    Some(data[..info.buffer_size()].chunks(3).map(|c| to_argb8(255, c[0], c[1], c[2])).collect())
+}
+
+fn channel_difference(a: u32, b: u32) -> u8 {
+    let (_, r0, g0, b0) = from_argb8(a);
+    let (_, r1, g1, b1) = from_argb8(b);
+    r0.abs_diff(r1).max(g0.abs_diff(g1)).max(b0.abs_diff(b1))
+}
+
+fn assert_golden(name: &str, actual: &[u32]) {
+    let golden_path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("tests/golden").join(format!("{}.png", name));
+
+    if std::env::var_os("GOLDEN_UPDATE").is_some() {
+        write_color(&golden_path, actual, WIDTH, HEIGHT).unwrap();
+        return;
+    }
+
+    let expected = read_png(&golden_path)
+        .unwrap_or_else(|| panic!("missing golden image {}, run with GOLDEN_UPDATE=1 to create it", golden_path.display()));
+
+    let mismatched = actual
+        .iter()
+        .zip(&expected)
+        .filter(|(&a, &e)| channel_difference(a, e) > TOLERANCE)
+        .count();
+    if mismatched == 0 {
+        return;
+    }
+
+    //matching pixels are dimmed so the red ones stand out
+    let diff: Vec<u32> = actual
+        .iter()
+        .zip(&expected)
+        .map(|(&a, &e)| {
+            if channel_difference(a, e) > TOLERANCE {
+                to_argb8(255, 255, 0, 0)
+            } else {
+                let (_, r, g, b) = from_argb8(e);
+                to_argb8(255, r / 4, g / 4, b / 4)
+            }
+        })
+        .collect();
+
+    let out_dir = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join("golden");
+    std::fs::create_dir_all(&out_dir).unwrap();
+    let actual_path = out_dir.join(format!("{}.actual.png", name));
+    let diff_path = out_dir.join(format!("{}.diff.png", name));
+    write_color(&actual_path, actual, WIDTH, HEIGHT).unwrap();
+    write_color(&diff_path, &diff, WIDTH, HEIGHT).unwrap();
+
+    panic!(
+        "{}: {} pixels differ from {} by more than {}, see {} and {}",
+        name,
+        mismatched,
+        golden_path.display(),
+        TOLERANCE,
+        actual_path.display(),
+        diff_path.display()
+    );
+}
+
+#[test]
+fn single_triangle() {
+    let normal = Vec3::Z;
+    let vertices = [
+        vertex(glam::vec3(-1.0, -1.0, 0.0), normal, glam::vec3(1.0, 0.0, 0.0), glam::Vec2::ZERO),
+        vertex(glam::vec3(1.0, -1.0, 0.0), normal, glam::vec3(0.0, 1.0, 0.0), glam::Vec2::ZERO),
+        vertex(glam::vec3(0.0, 1.0, 0.0), normal, glam::vec3(0.0, 0.0, 1.0), glam::Vec2::ZERO),
+    ];
+    let mesh = Mesh::from_vertices(&[UVec3::new(0, 1, 2)], &vertices);
+
+    let image = render(&mesh, None, &Mat4::IDENTITY, &camera_at(glam::vec3(0.0, 0.0, 3.0)), RenderType::Std);
+    assert_golden("single_triangle", &image);
+}
+
+#[test]
+fn near_clipped_triangle() {
+    //floor triangle running from in front of the camera to behind it, has to be cut at the near plane
+    let normal = Vec3::Y;
+    let vertices = [
+        vertex(glam::vec3(-2.0, -0.5, -6.0), normal, glam::vec3(1.0, 0.2, 0.2), glam::Vec2::ZERO),
+        vertex(glam::vec3(0.0, -0.5, 3.0), normal, glam::vec3(0.2, 1.0, 0.2), glam::Vec2::ZERO),
+        vertex(glam::vec3(2.0, -0.5, -6.0), normal, glam::vec3(0.2, 0.2, 1.0), glam::Vec2::ZERO),
+    ];
+    let mesh = Mesh::from_vertices(&[UVec3::new(0, 1, 2)], &vertices);
+
+    let image = render(&mesh, None, &Mat4::IDENTITY, &camera_at(Vec3::ZERO), RenderType::Std);
+    assert_golden("near_clipped_triangle", &image);
+}
+
+#[test]
+fn textured_quad() {
+    let texture = checker_texture(8);
+    //turned a bit so perspective correct uvs matter
+    let model = Mat4::from_rotation_y(0.6);
+
+    let image = render(&quad(Vec3::Z), Some(&texture), &model, &camera_at(glam::vec3(0.0, 0.0, 3.0)), RenderType::Std);
+    assert_golden("textured_quad", &image);
+}
+
+#[test]
+fn depth_mode() {
+    //tilted back so depth runs from top to bottom
+    let model = Mat4::from_rotation_x(-0.8);
+
+    let image = render(&quad(Vec3::Z), None, &model, &camera_at(glam::vec3(0.0, 0.0, 3.0)), RenderType::Depth);
+    assert_golden("depth_mode", &image);
+}